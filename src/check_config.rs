@@ -0,0 +1,94 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use crate::config::{Config, ConfigErr};
+
+pub fn check_config(path: Option<PathBuf>) -> ExitCode {
+    let (sources, result) = match path {
+        Some(path) => (vec![path.clone()], Config::from_path(path)),
+        None => (Config::sources(), Config::from_xdg_dirs()),
+    };
+
+    let name = match sources.as_slice() {
+        [] => Config::default_path().display().to_string(),
+        sources => sources
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+    };
+
+    let (config, mut problems) = match result {
+        Ok(loaded) => loaded,
+        Err(ConfigErr::NotFound) => {
+            eprintln!("error: {}: config file not found", name);
+            return ExitCode::FAILURE;
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // unknown keys are only warnings at runtime, but should fail CI. A merged config can't
+    // tell which file a value came from, so its problems get no file prefix
+    problems.extend(
+        config
+            .validate()
+            .into_iter()
+            .map(|problem| match sources.as_slice() {
+                [path] => format!("{}: {}", path.display(), problem),
+                _ => problem,
+            }),
+    );
+
+    if problems.is_empty() {
+        println!("{}: ok", name);
+        return ExitCode::SUCCESS;
+    }
+
+    for problem in &problems {
+        eprintln!("error: {}", problem);
+    }
+
+    eprintln!("{} problem(s) found", problems.len());
+    ExitCode::FAILURE
+}
+
+pub fn print_schema() -> ExitCode {
+    print!("{}", Config::schema());
+    ExitCode::SUCCESS
+}
+
+pub fn print_config(path: Option<PathBuf>) -> ExitCode {
+    let sources = match path {
+        Some(path) => vec![path],
+        None => Config::sources(),
+    };
+
+    let table = match Config::merged_table(&sources) {
+        Ok(table) => table,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let output = match toml::to_string_pretty(&table) {
+        Ok(output) => output,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    for source in &sources {
+        println!("# {}", source.display());
+    }
+
+    if !sources.is_empty() {
+        println!();
+    }
+
+    print!("{}", output);
+    ExitCode::SUCCESS
+}
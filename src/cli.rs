@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use clap::{Parser, ValueEnum};
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Args {
+    /// Path to the config file (default: merge the system, user and config.d files)
+    #[arg(short, long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Enable debug logging
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Log output format
+    #[arg(long, value_enum, default_value_t = LogFormat::Pretty)]
+    pub log_format: LogFormat,
+
+    /// Take over the bus name from a running instance
+    #[arg(long)]
+    pub replace: bool,
+
+    /// Connect to this D-Bus address instead of the session bus
+    #[arg(long, value_name = "ADDRESS")]
+    pub address: Option<String>,
+
+    /// Validate the config file and exit
+    #[arg(long, value_name = "PATH", num_args = 0..=1)]
+    pub check_config: Option<Option<PathBuf>>,
+
+    /// Print the effective config after merging all config files and exit
+    #[arg(long)]
+    pub print_config: bool,
+
+    /// Print the JSON Schema of the config file and exit
+    #[arg(long)]
+    pub print_schema: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum LogFormat {
+    Json,
+    Pretty,
+}
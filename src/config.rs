@@ -1,28 +1,133 @@
-use std::{env, fs, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
 
+use schemars::JsonSchema;
 use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use toml::{
+    Spanned, Table, Value,
+    de::{DeTable, Deserializer},
+};
 
 use crate::{
     constants::CONFIG_APP_NAME,
     portals::{
-        appchooser::config::AppChooserConfig, secret::config::SecretConfig,
-        settings::config::SettingsConfig,
+        access::config::AccessConfig, account::config::AccountConfig,
+        appchooser::config::AppChooserConfig, background::config::BackgroundConfig,
+        dynamic_launcher::config::DynamicLauncherConfig, email::config::EmailConfig,
+        inhibit::config::InhibitConfig, notification::config::NotificationConfig,
+        print::config::PrintConfig, screenshot::config::ScreenshotConfig,
+        secret::config::SecretConfig, settings::config::SettingsConfig,
+        wallpaper::config::WallpaperConfig,
     },
     terminal::Terminal,
+    utils::xdg,
 };
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct Config {
     pub terminal: Option<Terminal>,
     pub settings: Option<SettingsConfig>,
     pub appchooser: Option<AppChooserConfig>,
     pub secret: Option<SecretConfig>,
+    pub screenshot: Option<ScreenshotConfig>,
+    pub inhibit: Option<InhibitConfig>,
+    pub notification: Option<NotificationConfig>,
+    pub account: Option<AccountConfig>,
+    pub email: Option<EmailConfig>,
+    pub print: Option<PrintConfig>,
+    pub wallpaper: Option<WallpaperConfig>,
+    pub access: Option<AccessConfig>,
+    #[serde(rename = "dynamic-launcher")]
+    pub dynamic_launcher: Option<DynamicLauncherConfig>,
+    pub background: Option<BackgroundConfig>,
+    // profiles are applied to the table before it is deserialized, this is only here for the schema
+    #[allow(dead_code)]
+    #[schemars(with = "Option<HashMap<String, serde_json::Map<String, serde_json::Value>>>")]
+    pub profile: Option<HashMap<String, Table>>,
 }
 
+// used when there is no usable config: only the Settings and AppChooser portals are enabled,
+// the AppChooser answers with errors until a runner is configured
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            terminal: None,
+            settings: Some(SettingsConfig {
+                enabled: true,
+                ..Default::default()
+            }),
+            appchooser: Some(AppChooserConfig {
+                enabled: true,
+                ..Default::default()
+            }),
+            secret: None,
+            screenshot: None,
+            inhibit: None,
+            notification: None,
+            account: None,
+            email: None,
+            print: None,
+            wallpaper: None,
+            access: None,
+            dynamic_launcher: None,
+            background: None,
+            profile: None,
+        }
+    }
+}
+
+#[derive(Debug)]
 pub enum ConfigErr {
     NotFound,
     IOError(std::io::Error),
-    ParseError(toml::de::Error),
+    ParseError {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    Invalid {
+        sources: Vec<PathBuf>,
+        message: String,
+    },
+}
+
+impl Display for ConfigErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigErr::NotFound => write!(f, "config file not found"),
+            ConfigErr::IOError(err) => write!(f, "could not read config file: {}", err),
+            ConfigErr::ParseError {
+                path,
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "{}:{}:{}: {}",
+                path.display(),
+                line,
+                column,
+                message.trim()
+            ),
+            ConfigErr::Invalid { sources, message } => write!(
+                f,
+                "{} (merged from {})",
+                message.trim(),
+                sources
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }
+    }
 }
 
 impl From<std::io::Error> for ConfigErr {
@@ -31,35 +136,704 @@ impl From<std::io::Error> for ConfigErr {
     }
 }
 
-impl From<toml::de::Error> for ConfigErr {
-    fn from(value: toml::de::Error) -> Self {
-        ConfigErr::ParseError(value)
+// translates the byte offset of the error into a 1-based line/column position, columns count
+// characters
+fn error_position(data: &str, err: &toml::de::Error) -> (usize, usize) {
+    let offset = err.span().map(|span| span.start).unwrap_or_default();
+    let before = &data[..offset.min(data.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let column = before[line_start..].chars().count() + 1;
+
+    (line, column)
+}
+
+fn parse_error(path: PathBuf, data: &str, err: toml::de::Error) -> ConfigErr {
+    let (line, column) = error_position(data, &err);
+
+    ConfigErr::ParseError {
+        path,
+        line,
+        column,
+        message: err.message().to_string(),
+    }
+}
+
+// where deserializing only the section `key` of the document fails
+fn section_error_position(data: &str, key: &str) -> Option<(usize, usize)> {
+    let document = DeTable::parse(data).ok()?;
+    let span = document.span();
+    let section: DeTable = document
+        .into_inner()
+        .into_iter()
+        .filter(|(name, _)| name.get_ref() == key)
+        .collect();
+
+    let err = Config::deserialize(Deserializer::from(Spanned::new(span, section))).err()?;
+    Some(error_position(data, &err))
+}
+
+// config directories from lowest to highest priority: /etc/xdg unless $XDG_CONFIG_DIRS lists
+// it, $XDG_CONFIG_DIRS in reverse and $XDG_CONFIG_HOME. A directory listed twice counts where
+// it is more important
+fn ordered_config_dirs(config_dirs: Vec<PathBuf>, config_home: PathBuf) -> Vec<PathBuf> {
+    let mut dirs = vec![config_home];
+
+    for dir in config_dirs.into_iter().chain([PathBuf::from("/etc/xdg")]) {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
     }
+
+    dirs.into_iter()
+        .rev()
+        .map(|dir| dir.join(CONFIG_APP_NAME))
+        .collect()
+}
+
+// sections are merged key by key, these tables are merged all the way down
+fn is_deep_merged(path: &[&str]) -> bool {
+    matches!(
+        path,
+        [_] | ["appchooser", "defaults"] | ["settings", "dict"] | ["settings", "dict", _]
+    )
+}
+
+fn merge(base: &mut Table, overlay: Table, path: &[&str]) {
+    for (key, value) in overlay {
+        let mut key_path = path.to_vec();
+        key_path.push(key.as_str());
+
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(overlay)) if is_deep_merged(&key_path) => {
+                merge(base, overlay, &key_path)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+// overlay the [profile.<desktop>] sections matching $XDG_CURRENT_DESKTOP,
+// the first desktop in the list takes precedence
+fn apply_profiles(table: &mut Table, desktops: &[String]) {
+    let Some(Value::Table(profiles)) = table.remove("profile") else {
+        return;
+    };
+
+    for desktop in desktops.iter().rev() {
+        let profile = profiles
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(desktop))
+            .map(|(_, profile)| profile);
+
+        if let Some(Value::Table(profile)) = profile {
+            tracing::debug!("Applying config profile for {}", desktop);
+            merge(table, profile.clone(), &[]);
+        }
+    }
+}
+
+fn key_path(path: &[String]) -> String {
+    let mut joined = String::new();
+
+    for key in path {
+        if key.starts_with('[') {
+            joined.push_str(key);
+            continue;
+        }
+
+        if !joined.is_empty() {
+            joined.push('.');
+        }
+
+        if !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            joined.push_str(key);
+        } else {
+            joined.push_str(&format!("{:?}", key));
+        }
+    }
+
+    joined
+}
+
+fn resolve<'a>(schema: &'a JsonValue, root: &'a JsonValue) -> &'a JsonValue {
+    schema
+        .get("$ref")
+        .and_then(|reference| reference.as_str())
+        .and_then(|reference| reference.strip_prefix("#/$defs/"))
+        .and_then(|name| root.get("$defs")?.get(name))
+        .unwrap_or(schema)
+}
+
+fn kind_matches(schema: &JsonValue, value: &JsonValue) -> bool {
+    let kind = match value {
+        JsonValue::Object(_) => "object",
+        JsonValue::Array(_) => "array",
+        JsonValue::String(_) => "string",
+        JsonValue::Bool(_) => "boolean",
+        JsonValue::Number(n) if n.is_f64() => "number",
+        JsonValue::Number(_) => "integer",
+        JsonValue::Null => "null",
+    };
+
+    let matches = |t: &JsonValue| t == kind || (t == "number" && kind == "integer");
+
+    match schema.get("type") {
+        Some(JsonValue::Array(types)) => types.iter().any(matches),
+        Some(t) => matches(t),
+        None => true,
+    }
+}
+
+// whether a variant of an untagged enum could have been meant for the value
+fn accepts(schema: &JsonValue, value: &JsonValue, root: &JsonValue) -> bool {
+    if !kind_matches(schema, value) {
+        return false;
+    }
+
+    match (value, schema.get("items")) {
+        (JsonValue::Array(items), Some(item_schema)) => {
+            let item_schema = resolve(item_schema, root);
+            items.iter().all(|item| kind_matches(item_schema, item))
+        }
+        _ => true,
+    }
+}
+
+// walks the value along the generated schema, serde itself is lenient about unknown keys
+fn find_unknown_keys(
+    value: &JsonValue,
+    schema: &JsonValue,
+    root: &JsonValue,
+    path: &mut Vec<String>,
+    unknown: &mut Vec<String>,
+) {
+    let schema = resolve(schema, root);
+
+    if let Some(variants) = schema
+        .get("anyOf")
+        .or(schema.get("oneOf"))
+        .and_then(|variants| variants.as_array())
+    {
+        // the variant with the fewest unknown keys is most likely the one that was meant
+        let best = variants
+            .iter()
+            .map(|variant| resolve(variant, root))
+            .filter(|variant| accepts(variant, value, root))
+            .map(|variant| {
+                let mut found = Vec::new();
+                find_unknown_keys(value, variant, root, path, &mut found);
+                found
+            })
+            .min_by_key(|found| found.len());
+
+        unknown.extend(best.unwrap_or_default());
+        return;
+    }
+
+    match value {
+        JsonValue::Object(map) => {
+            let properties = schema.get("properties");
+            let additional = schema.get("additionalProperties");
+
+            for (key, value) in map {
+                path.push(key.clone());
+
+                match (properties.and_then(|p| p.get(key)), additional) {
+                    (Some(schema), _) => find_unknown_keys(value, schema, root, path, unknown),
+                    (None, Some(JsonValue::Bool(false))) => unknown.push(key_path(path)),
+                    (None, Some(schema)) if schema.is_object() => {
+                        find_unknown_keys(value, schema, root, path, unknown)
+                    }
+                    _ => {}
+                }
+
+                path.pop();
+            }
+        }
+        JsonValue::Array(items) => {
+            if let Some(schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    path.push(format!("[{}]", i));
+                    find_unknown_keys(item, schema, root, path, unknown);
+                    path.pop();
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// the keys of a config file the Config type does not know, profiles are checked like the
+// top level since they are overlays of it
+fn unknown_keys(table: &Table) -> Vec<String> {
+    let schema = schemars::schema_for!(Config).to_value();
+
+    let Ok(value) = serde_json::to_value(table) else {
+        return Vec::new();
+    };
+
+    let mut unknown = Vec::new();
+    find_unknown_keys(&value, &schema, &schema, &mut Vec::new(), &mut unknown);
+
+    if let Some(JsonValue::Object(profiles)) = value.get("profile") {
+        for (name, profile) in profiles {
+            let mut path = vec!["profile".to_string(), name.clone()];
+            find_unknown_keys(profile, &schema, &schema, &mut path, &mut unknown);
+        }
+    }
+
+    unknown
 }
 
 impl Config {
-    pub fn from_xdg_dirs() -> Result<Config, ConfigErr> {
-        let config_home = env::var("XDG_CONFIG_HOME")
-            .map(|path| path.into())
-            .or_else(|_| {
-                let home_dir: PathBuf = env::var("HOME").expect("cant evaluate HOME").into();
-                Ok::<PathBuf, ConfigErr>(home_dir.join(".config"))
-            })?
-            .join(CONFIG_APP_NAME)
-            .join("config.toml");
+    pub fn default_path() -> PathBuf {
+        xdg::config_home().join(CONFIG_APP_NAME).join("config.toml")
+    }
+
+    fn config_dirs() -> Vec<PathBuf> {
+        ordered_config_dirs(xdg::config_dirs(), xdg::config_home())
+    }
+
+    // config.toml followed by the config-<desktop>.toml files, like <desktop>-portals.conf
+    fn config_files() -> Vec<String> {
+        let mut files = vec!["config.toml".to_string()];
+
+        for desktop in xdg::current_desktops().iter().rev() {
+            files.push(format!("config-{}.toml", desktop.to_lowercase()));
+        }
+
+        files
+    }
+
+    fn drop_ins(dir: &Path) -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(dir.join("config.d")) else {
+            return Vec::new();
+        };
+
+        let mut drop_ins: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+
+        drop_ins.sort();
+        drop_ins
+    }
+
+    // per directory the config files followed by its config.d/*.toml drop-ins in lexical order,
+    // so a more important directory always wins. A drop-in with the same name in a more
+    // important directory replaces the other one
+    pub fn sources() -> Vec<PathBuf> {
+        let dirs = Config::config_dirs();
+        let names = Config::config_files();
+
+        let drop_ins: Vec<Vec<PathBuf>> = dirs.iter().map(|dir| Config::drop_ins(dir)).collect();
+
+        let mut sources = Vec::new();
+
+        for (i, dir) in dirs.iter().enumerate() {
+            sources.extend(
+                names
+                    .iter()
+                    .map(|name| dir.join(name))
+                    .filter(|path| path.is_file()),
+            );
+
+            sources.extend(
+                drop_ins[i]
+                    .iter()
+                    .filter(|path| {
+                        !drop_ins[i + 1..]
+                            .iter()
+                            .flatten()
+                            .any(|other| other.file_name() == path.file_name())
+                    })
+                    .cloned(),
+            );
+        }
+
+        sources
+    }
 
-        Config::from_path(config_home)
+    fn read_table(path: &PathBuf) -> Result<(Table, String), ConfigErr> {
+        let data = fs::read_to_string(path)?;
+        let table = toml::from_str(&data).map_err(|err| parse_error(path.clone(), &data, err))?;
+        Ok((table, data))
     }
 
-    pub fn from_path(path: PathBuf) -> Result<Config, ConfigErr> {
+    // reads the sources in order, applies the profiles of the current desktop and merges them.
+    // `inspect` sees each file as it was written
+    fn merge_sources(
+        sources: &[PathBuf],
+        mut inspect: impl FnMut(&PathBuf, &Table, &str),
+    ) -> Result<Table, ConfigErr> {
+        let desktops = xdg::current_desktops();
+        let mut merged = Table::new();
+
+        for path in sources {
+            let (mut table, data) = Config::read_table(path)?;
+
+            inspect(path, &table, &data);
+
+            apply_profiles(&mut table, &desktops);
+            merge(&mut merged, table, &[]);
+        }
+
+        Ok(merged)
+    }
+
+    pub fn merged_table(sources: &[PathBuf]) -> Result<Table, ConfigErr> {
+        Config::merge_sources(sources, |_, _, _| {})
+    }
+
+    pub fn from_xdg_dirs() -> Result<(Config, Vec<String>), ConfigErr> {
+        Config::load(Config::sources())
+    }
+
+    pub fn from_path(path: PathBuf) -> Result<(Config, Vec<String>), ConfigErr> {
         if !path.exists() {
             return Err(ConfigErr::NotFound);
         }
 
-        let data = fs::read_to_string(path)?;
+        Config::load(vec![path])
+    }
+
+    // parses leniently: unknown keys are ignored and a section that does not parse is left
+    // out, both are returned as problems so the daemon can warn and --check-config can fail
+    fn load(sources: Vec<PathBuf>) -> Result<(Config, Vec<String>), ConfigErr> {
+        if sources.is_empty() {
+            return Err(ConfigErr::NotFound);
+        }
+
+        if sources.len() > 1 {
+            tracing::info!("Merging config from {:?}", sources);
+        }
+
+        let mut problems = Vec::new();
+        let mut last_data = String::new();
+
+        let merged = Config::merge_sources(&sources, |path, table, data| {
+            for key in unknown_keys(table) {
+                problems.push(format!("{}: unknown key {}", path.display(), key));
+            }
+
+            last_data = data.to_string();
+        })?;
+
+        if let Ok(config) = Config::deserialize(Value::Table(merged.clone())) {
+            return Ok((config, problems));
+        }
+
+        let origin = sources
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        // a single file can be parsed again to get the position of the error
+        let position = |key: &str| {
+            let [path] = sources.as_slice() else {
+                return None;
+            };
+
+            let (line, column) = section_error_position(&last_data, key)?;
+            Some(format!("{}:{}:{}", path.display(), line, column))
+        };
+
+        let mut valid = Table::new();
+
+        for (key, value) in merged {
+            let section = Table::from_iter([(key.clone(), value.clone())]);
+
+            match Config::deserialize(Value::Table(section)) {
+                Ok(_) => {
+                    valid.insert(key, value);
+                }
+                Err(err) => problems.push(format!(
+                    "{}: ignoring [{}]: {}",
+                    position(&key).unwrap_or_else(|| origin.clone()),
+                    key,
+                    err.message().trim()
+                )),
+            }
+        }
+
+        let config =
+            Config::deserialize(Value::Table(valid)).map_err(|err| ConfigErr::Invalid {
+                sources,
+                message: err.message().to_string(),
+            })?;
+
+        Ok((config, problems))
+    }
+
+    pub fn schema() -> String {
+        let schema = schemars::schema_for!(Config);
+        let mut schema = serde_json::to_string_pretty(&schema).expect("schema is valid json");
+        schema.push('\n');
+        schema
+    }
+
+    // semantic checks that go beyond what serde can express
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if let Some(config) = &self.appchooser {
+            problems.extend(config.validate());
+        }
+
+        if let Some(config) = &self.settings {
+            problems.extend(config.validate());
+        }
+
+        if let Some(config) = &self.screenshot {
+            problems.extend(config.validate());
+        }
+
+        if let Some(config) = &self.inhibit {
+            problems.extend(config.validate());
+        }
+
+        if let Some(config) = &self.account {
+            problems.extend(config.validate());
+        }
+
+        if let Some(config) = &self.email {
+            problems.extend(config.validate());
+        }
+
+        if let Some(config) = &self.print {
+            problems.extend(config.validate());
+        }
+
+        if let Some(config) = &self.wallpaper {
+            problems.extend(config.validate());
+        }
+
+        if let Some(config) = &self.access {
+            problems.extend(config.validate());
+        }
+
+        if let Some(config) = &self.dynamic_launcher {
+            problems.extend(config.validate());
+        }
+
+        if let Some(config) = &self.background {
+            problems.extend(config.validate());
+        }
+
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use toml::Table;
+
+    use super::{
+        Config, ConfigErr, apply_profiles, merge, ordered_config_dirs, parse_error, unknown_keys,
+    };
+
+    #[test]
+    fn schema_is_up_to_date() {
+        // regenerate with: xdg-desktop-portal-zenzai --print-schema > share/config.schema.json
+        assert_eq!(
+            include_str!("../share/config.schema.json"),
+            Config::schema(),
+            "share/config.schema.json is out of date"
+        );
+    }
+
+    #[test]
+    fn defaults_only_enable_settings_and_the_appchooser() {
+        let config = Config::default();
+
+        assert!(config.settings.is_some_and(|config| config.enabled));
+        assert!(config.appchooser.is_some_and(|config| config.enabled));
+        assert!(config.notification.is_none());
+    }
+
+    #[test]
+    fn parse_error_counts_columns_in_characters() {
+        let data = "settings = { accent-color = \"äöü\", contrast = \"bad\" }\n";
+        let err = toml::from_str::<Config>(data).unwrap_err();
+
+        let ConfigErr::ParseError { line, column, .. } =
+            parse_error(PathBuf::from("config.toml"), data, err)
+        else {
+            panic!("expected a parse error");
+        };
+
+        assert_eq!((line, column), (1, 47));
+    }
+
+    #[test]
+    fn invalid_sections_are_reported_once_with_their_position() {
+        let path = std::env::temp_dir().join(format!(
+            "zenzai-config-test-{:08x}.toml",
+            rand::random::<u32>()
+        ));
+        std::fs::write(
+            &path,
+            "[settings]\nenabled = true\ncontrast = \"bad\"\n\n[secret]\nenabled = true\n",
+        )
+        .unwrap();
+
+        let (config, problems) = Config::from_path(path.clone()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(config.settings.is_none());
+        assert!(config.secret.is_some());
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(
+            problems[0].starts_with(&format!("{}:3:12: ignoring [settings]: ", path.display())),
+            "{:?}",
+            problems
+        );
+    }
+
+    #[test]
+    fn config_dirs_follow_the_xdg_order() {
+        let dirs = |config_dirs: &[&str]| -> Vec<String> {
+            ordered_config_dirs(
+                config_dirs.iter().map(PathBuf::from).collect(),
+                PathBuf::from("/home/user/.config"),
+            )
+            .iter()
+            .map(|dir| dir.parent().unwrap().display().to_string())
+            .collect()
+        };
+
+        assert_eq!(
+            dirs(&["/etc/xdg", "/opt/site"]),
+            ["/opt/site", "/etc/xdg", "/home/user/.config"]
+        );
+        assert_eq!(
+            dirs(&["/opt/site"]),
+            ["/etc/xdg", "/opt/site", "/home/user/.config"]
+        );
+        assert_eq!(
+            dirs(&["/opt/site", "/etc/xdg", "/opt/site"]),
+            ["/etc/xdg", "/opt/site", "/home/user/.config"]
+        );
+    }
+
+    #[test]
+    fn unknown_keys_are_found_in_nested_sections() {
+        let table = toml::from_str(
+            r#"
+            [settings]
+            enabeld = true
+
+            [appchooser.defaults]
+            "text/plain" = { command = "sh", terminl = true }
+            "text/html" = [{ command = "sh", x = 1 }]
+            "image/png" = ["imv.desktop"]
+
+            [profile.niri.settings]
+            colour = "dark"
+            "#,
+        )
+        .unwrap();
+
+        let mut unknown = unknown_keys(&table);
+        unknown.sort();
+
+        assert_eq!(
+            unknown,
+            [
+                r#"appchooser.defaults."text/html"[0].x"#,
+                r#"appchooser.defaults."text/plain".terminl"#,
+                "profile.niri.settings.colour",
+                "settings.enabeld",
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_replaces_keys_within_sections() {
+        let mut base: Table = toml::from_str(
+            r#"
+            [settings]
+            enabled = true
+            color-scheme = "dark"
+
+            [appchooser.defaults]
+            "text/plain" = ["vim.desktop"]
+            "#,
+        )
+        .unwrap();
+
+        let overlay: Table = toml::from_str(
+            r#"
+            [settings]
+            color-scheme = "light"
+
+            [appchooser.defaults]
+            "text/html" = ["firefox.desktop"]
+            "#,
+        )
+        .unwrap();
+
+        merge(&mut base, overlay, &[]);
+
+        let expected: Table = toml::from_str(
+            r#"
+            [settings]
+            enabled = true
+            color-scheme = "light"
+
+            [appchooser.defaults]
+            "text/plain" = ["vim.desktop"]
+            "text/html" = ["firefox.desktop"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(base, expected);
+    }
+
+    #[test]
+    fn first_matching_profile_wins() {
+        let mut table: Table = toml::from_str(
+            r#"
+            [settings]
+            color-scheme = "dark"
+            contrast = "normal"
+
+            [profile.niri.settings]
+            color-scheme = "light"
+
+            [profile.gnome.settings]
+            color-scheme = "prefer-dark"
+            contrast = "high"
+
+            [profile.kde.settings]
+            contrast = "low"
+            "#,
+        )
+        .unwrap();
+
+        apply_profiles(&mut table, &["niri".to_string(), "GNOME".to_string()]);
 
-        let config: Config = toml::from_str(data.as_str())?;
+        let expected: Table = toml::from_str(
+            r#"
+            [settings]
+            color-scheme = "light"
+            contrast = "high"
+            "#,
+        )
+        .unwrap();
 
-        Ok(config)
+        assert_eq!(table, expected);
     }
 }
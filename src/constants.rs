@@ -1,3 +1,3 @@
 pub const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
-pub const DBUS_NAME: &'static str = "org.freedesktop.impl.portal.desktop.zenzai";
-pub const CONFIG_APP_NAME: &'static str = "xdg-desktop-portal-zenzai";
+pub const DBUS_NAME: &str = "org.freedesktop.impl.portal.desktop.zenzai";
+pub const CONFIG_APP_NAME: &str = "xdg-desktop-portal-zenzai";
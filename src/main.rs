@@ -1,15 +1,26 @@
-use std::future::pending;
+use std::{process::ExitCode, time::Duration};
 
+use check_config::{check_config, print_config, print_schema};
+use clap::Parser;
+use cli::{Args, LogFormat};
 use config::{Config, ConfigErr};
 use constants::{APP_VERSION, DBUS_NAME};
+use futures_util::StreamExt;
 use portals::{
-    appchooser::service::AppChooserService, secret::service::SecretService,
-    settings::service::SettingsService,
+    access::service::AccessService, account::service::AccountService,
+    appchooser::service::AppChooserService, background::service::BackgroundService, dialog::Runner,
+    dynamic_launcher::service::DynamicLauncherService, email::service::EmailService,
+    inhibit::service::InhibitService, notification::service::NotificationService,
+    print::service::PrintService, screenshot::service::ScreenshotService,
+    secret::service::SecretService, settings::service::SettingsService,
+    wallpaper::service::WallpaperService,
 };
-use terminal::{command_path, terminal_from_env};
+use terminal::discover_terminal;
 use tracing_subscriber::EnvFilter;
-use zbus::{Result, conn::Builder};
+use zbus::{Result, conn::Builder, fdo::DBusProxy};
 
+mod check_config;
+mod cli;
 mod config;
 mod constants;
 mod portals;
@@ -17,43 +28,122 @@ mod terminal;
 mod utils;
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    if cfg!(debug_assertions) {
-        tracing_subscriber::fmt()
-            .with_max_level(tracing::Level::DEBUG)
-            .init();
+async fn main() -> Result<ExitCode> {
+    let args = Args::parse();
+
+    if let Some(path) = args.check_config {
+        return Ok(check_config(path.or(args.config)));
+    }
+
+    if args.print_schema {
+        return Ok(print_schema());
+    }
+
+    if args.print_config {
+        return Ok(print_config(args.config));
+    }
+
+    let filter = if args.verbose || cfg!(debug_assertions) {
+        EnvFilter::new("debug")
     } else {
-        tracing_subscriber::fmt()
-            .with_env_filter(EnvFilter::new("xdg-desktop-portal-zenzai"))
-            .init();
+        EnvFilter::new("xdg-desktop-portal-zenzai")
+    };
+
+    match args.log_format {
+        LogFormat::Json => tracing_subscriber::fmt()
+            .json()
+            .with_env_filter(filter)
+            .init(),
+        LogFormat::Pretty => tracing_subscriber::fmt().with_env_filter(filter).init(),
     }
 
     tracing::info!("xdg-desktop-portal-zenzai v{} started!", APP_VERSION);
 
-    let config = match Config::from_xdg_dirs() {
-        Ok(config) => config,
-        Err(ConfigErr::NotFound) => {
-            panic!("Could not find config file");
+    let config = match args
+        .config
+        .map_or_else(Config::from_xdg_dirs, Config::from_path)
+    {
+        Ok((config, problems)) => {
+            for problem in problems {
+                tracing::warn!("Config: {}", problem);
+            }
+
+            config
         }
-        Err(ConfigErr::IOError(err)) => {
-            panic!("Config IO Error: {err:?}");
+        Err(ConfigErr::NotFound) => {
+            tracing::warn!("Could not find config file, using built-in defaults");
+            Config::default()
         }
-        Err(ConfigErr::ParseError(err)) => {
-            panic!("Config Parse Error: {:?}", err.message());
+        Err(err) => {
+            tracing::error!("Config Error: {}", err);
+            tracing::error!("Falling back to built-in defaults");
+            Config::default()
         }
     };
 
     tracing::debug!("Config: {:?}", config);
 
-    let terminal = config.terminal.unwrap_or(terminal_from_env());
-    assert!(
-        command_path(&terminal).is_some(),
-        "Could not find terminal: {terminal:?}"
+    // only the AppChooser and Email portals need a terminal (for Terminal=true applications)
+    let needs_terminal = config
+        .appchooser
+        .as_ref()
+        .is_some_and(|config| config.enabled)
+        || config.email.as_ref().is_some_and(|config| config.enabled);
+
+    let terminal = if needs_terminal {
+        match discover_terminal(config.terminal) {
+            Some((terminal, source)) => {
+                tracing::info!("terminal: {:?} (from {})", terminal, source);
+                Some(terminal)
+            }
+            None => {
+                tracing::warn!(
+                    "Could not find a terminal, terminal applications can't be launched"
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // portals that ask the user something use the appchooser runner unless they have their own
+    let runner = config
+        .appchooser
+        .as_ref()
+        .and_then(|config| config.runner.clone());
+
+    let defaults = config
+        .appchooser
+        .as_ref()
+        .map(|config| config.defaults.clone())
+        .unwrap_or_default();
+
+    let launch_mode = config
+        .appchooser
+        .as_ref()
+        .and_then(|config| config.launch_mode)
+        .unwrap_or_default();
+
+    let grace_period = Duration::from_millis(
+        config
+            .appchooser
+            .as_ref()
+            .and_then(|config| config.launch_check_ms)
+            .unwrap_or_default(),
     );
 
     let mut any_enabled = false;
 
-    let mut conn = Builder::session()?.name(DBUS_NAME)?;
+    let builder = match &args.address {
+        Some(address) => Builder::address(address.as_str())?,
+        None => Builder::session()?,
+    };
+
+    let mut conn = builder
+        .name(DBUS_NAME)?
+        .allow_name_replacements(true)
+        .replace_existing_names(args.replace);
 
     if let Some(config) = config.settings
         && config.enabled
@@ -75,7 +165,10 @@ async fn main() -> Result<()> {
         tracing::info!("portal: org.freedesktop.portal.AppChooser enabled!");
         conn = conn.serve_at(
             "/org/freedesktop/portal/desktop",
-            AppChooserService { terminal, config },
+            AppChooserService {
+                terminal: terminal.clone(),
+                config,
+            },
         )?;
     }
 
@@ -88,13 +181,157 @@ async fn main() -> Result<()> {
         conn = conn.serve_at("/org/freedesktop/portal/desktop", SecretService)?;
     }
 
+    if let Some(config) = config.screenshot
+        && config.enabled
+    {
+        any_enabled = true;
+
+        tracing::info!("portal: org.freedesktop.portal.Screenshot enabled!");
+        conn = conn.serve_at(
+            "/org/freedesktop/portal/desktop",
+            ScreenshotService { config },
+        )?;
+    }
+
+    if let Some(config) = config.inhibit
+        && config.enabled
+    {
+        any_enabled = true;
+
+        tracing::info!("portal: org.freedesktop.portal.Inhibit enabled!");
+        conn = conn.serve_at(
+            "/org/freedesktop/portal/desktop",
+            InhibitService::new(config),
+        )?;
+    }
+
+    if let Some(config) = config.notification
+        && config.enabled
+    {
+        any_enabled = true;
+
+        tracing::info!("portal: org.freedesktop.portal.Notification enabled!");
+        conn = conn.serve_at(
+            "/org/freedesktop/portal/desktop",
+            NotificationService::default(),
+        )?;
+    }
+
+    if let Some(config) = config.account
+        && config.enabled
+    {
+        any_enabled = true;
+
+        tracing::info!("portal: org.freedesktop.portal.Account enabled!");
+        conn = conn.serve_at(
+            "/org/freedesktop/portal/desktop",
+            AccountService {
+                runner: Runner::new(config.runner.clone(), runner.clone()),
+                config,
+            },
+        )?;
+    }
+
+    if let Some(config) = config.email
+        && config.enabled
+    {
+        any_enabled = true;
+
+        tracing::info!("portal: org.freedesktop.portal.Email enabled!");
+        conn = conn.serve_at(
+            "/org/freedesktop/portal/desktop",
+            EmailService {
+                config,
+                terminal,
+                runner: runner.clone(),
+                defaults,
+                launch_mode,
+                grace_period,
+            },
+        )?;
+    }
+
+    if let Some(config) = config.print
+        && config.enabled
+    {
+        any_enabled = true;
+
+        tracing::info!("portal: org.freedesktop.portal.Print enabled!");
+        conn = conn.serve_at(
+            "/org/freedesktop/portal/desktop",
+            PrintService::new(Runner::new(config.runner.clone(), runner.clone()), config),
+        )?;
+    }
+
+    if let Some(config) = config.wallpaper
+        && config.enabled
+    {
+        any_enabled = true;
+
+        tracing::info!("portal: org.freedesktop.portal.Wallpaper enabled!");
+        conn = conn.serve_at(
+            "/org/freedesktop/portal/desktop",
+            WallpaperService::new(Runner::new(config.runner.clone(), runner.clone()), config),
+        )?;
+    }
+
+    if let Some(config) = config.access
+        && config.enabled
+    {
+        any_enabled = true;
+
+        tracing::info!("portal: org.freedesktop.portal.Access enabled!");
+        conn = conn.serve_at(
+            "/org/freedesktop/portal/desktop",
+            AccessService {
+                runner: Runner::new(config.runner, runner.clone()),
+            },
+        )?;
+    }
+
+    if let Some(config) = config.dynamic_launcher
+        && config.enabled
+    {
+        any_enabled = true;
+
+        tracing::info!("portal: org.freedesktop.portal.DynamicLauncher enabled!");
+        conn = conn.serve_at(
+            "/org/freedesktop/portal/desktop",
+            DynamicLauncherService {
+                runner: Runner::new(config.runner.clone(), runner.clone()),
+                config,
+            },
+        )?;
+    }
+
+    if let Some(config) = config.background
+        && config.enabled
+    {
+        any_enabled = true;
+
+        tracing::info!("portal: org.freedesktop.portal.Background enabled!");
+        conn = conn.serve_at(
+            "/org/freedesktop/portal/desktop",
+            BackgroundService::new(Runner::new(config.runner.clone(), runner.clone()), config),
+        )?;
+    }
+
     if !any_enabled {
-        tracing::error!("No portal was enbaled, quitting");
-        return Ok(());
+        tracing::error!("No portal was enabled, quitting");
+        return Ok(ExitCode::FAILURE);
     }
 
-    let _conn = conn.build().await?;
-    pending::<()>().await;
+    let conn = conn.build().await?;
+
+    // another instance started with --replace took over our name
+    let mut name_lost = DBusProxy::new(&conn).await?.receive_name_lost().await?;
+
+    while let Some(signal) = name_lost.next().await {
+        if signal.args()?.name() == DBUS_NAME {
+            tracing::info!("Lost {} to another instance, quitting", DBUS_NAME);
+            break;
+        }
+    }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
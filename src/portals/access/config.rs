@@ -0,0 +1,22 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::portals::{appchooser::config::RunnerType, dialog::validate_runner};
+
+#[derive(Debug, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "kebab-case")]
+#[schemars(deny_unknown_fields)]
+pub struct AccessConfig {
+    pub enabled: bool,
+    pub runner: Option<RunnerType>,
+}
+
+impl AccessConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        problems.extend(validate_runner("access.runner", self.runner.as_ref()));
+
+        problems
+    }
+}
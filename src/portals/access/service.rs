@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+
+use zbus::{fdo, interface};
+use zvariant::{ObjectPath, OwnedValue, Value};
+
+use crate::{portals::dialog::Runner, utils::options::string_option};
+
+// an extra question of the dialog, without options it is a yes/no checkbox
+#[derive(Debug)]
+struct Choice {
+    id: String,
+    label: String,
+    options: Vec<(String, String)>,
+    initial: String,
+}
+
+impl Choice {
+    fn from_value(value: &Value<'_>) -> Option<Choice> {
+        let Value::Structure(choice) = value else {
+            return None;
+        };
+
+        let [
+            Value::Str(id),
+            Value::Str(label),
+            Value::Array(options),
+            Value::Str(initial),
+        ] = choice.fields()
+        else {
+            return None;
+        };
+
+        let options = options
+            .iter()
+            .filter_map(|option| match option {
+                Value::Structure(option) => match option.fields() {
+                    [Value::Str(id), Value::Str(label)] => {
+                        Some((id.to_string(), label.to_string()))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+
+        Some(Choice {
+            id: id.to_string(),
+            label: label.to_string(),
+            options,
+            initial: initial.to_string(),
+        })
+    }
+
+    // (id, label) pairs offered to the runner, the initial selection comes first
+    fn picks(&self) -> Vec<(String, String)> {
+        let mut picks = if self.options.is_empty() {
+            vec![
+                ("true".to_string(), "yes".to_string()),
+                ("false".to_string(), "no".to_string()),
+            ]
+        } else {
+            self.options.clone()
+        };
+
+        if let Some(i) = picks.iter().position(|(id, _)| *id == self.initial) {
+            let initial = picks.remove(i);
+            picks.insert(0, initial);
+        }
+
+        picks
+    }
+}
+
+pub struct AccessService {
+    pub runner: Runner,
+}
+
+#[interface(name = "org.freedesktop.impl.portal.Access")]
+impl AccessService {
+    #[zbus(property, name = "version")]
+    async fn version(&self) -> u32 {
+        1
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn access_dialog(
+        &self,
+        handle: ObjectPath<'_>,
+        app_id: &str,
+        parent_window: &str,
+        title: &str,
+        subtitle: &str,
+        body: &str,
+        options: HashMap<&str, Value<'_>>,
+    ) -> fdo::Result<(u32, HashMap<String, OwnedValue>)> {
+        tracing::debug!(
+            "AccessDialog called with handle: {:?}, app_id: {:?}, parent_window: {}, title: {:?}, subtitle: {:?}, body: {:?}, options: {:?}",
+            handle,
+            app_id,
+            parent_window,
+            title,
+            subtitle,
+            body,
+            options
+        );
+
+        let grant = string_option(&options, "grant_label").unwrap_or("Grant Access".to_string());
+        let deny = string_option(&options, "deny_label").unwrap_or("Deny Access".to_string());
+
+        let choices: Vec<Choice> = match options.get("choices") {
+            Some(Value::Array(choices)) => choices.iter().filter_map(Choice::from_value).collect(),
+            _ => Vec::new(),
+        };
+
+        // dmenu style runners only show single lines, so the whole dialog becomes the prompt
+        let prompt = [title, subtitle, body]
+            .into_iter()
+            .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(" - ");
+
+        let Some(granted) = self.pick(&prompt, &[(true, grant), (false, deny)]).await? else {
+            tracing::info!("Access dialog for {:?} was cancelled", app_id);
+            return Ok((1, HashMap::new()));
+        };
+
+        if !granted {
+            tracing::info!("Access for {:?} was denied: {}", app_id, prompt);
+            return Ok((1, HashMap::new()));
+        }
+
+        let mut selected: Vec<(String, String)> = Vec::new();
+
+        for choice in choices {
+            let Some(option) = self.pick(&choice.label, &choice.picks()).await? else {
+                tracing::info!("Access dialog for {:?} was cancelled", app_id);
+                return Ok((1, HashMap::new()));
+            };
+
+            selected.push((choice.id, option));
+        }
+
+        tracing::info!(
+            "Access for {:?} was granted: {} {:?}",
+            app_id,
+            prompt,
+            selected
+        );
+
+        let choices = OwnedValue::try_from(Value::from(selected))
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+
+        let mut m = HashMap::new();
+        m.insert("choices".to_string(), choices);
+
+        Ok((0, m))
+    }
+}
+
+impl AccessService {
+    // offers "prompt: label" lines and returns the value behind the selected one, None if nothing matched
+    async fn pick<T: Clone>(&self, prompt: &str, picks: &[(T, String)]) -> fdo::Result<Option<T>> {
+        let lines: Vec<String> = picks
+            .iter()
+            .map(|(_, label)| format!("{}: {}", prompt, label))
+            .collect();
+
+        let selection = self.runner.pick(&lines).await?;
+
+        Ok(selection.map(|i| picks[i].0.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use zvariant::{ObjectPath, Value};
+
+    use super::{AccessService, Choice};
+    use crate::portals::{
+        appchooser::config::{Command, RunnerType},
+        dialog::Runner,
+    };
+
+    // a runner that always selects the lines matching one of `patterns`
+    fn service(patterns: &[&str]) -> AccessService {
+        let arguments = patterns
+            .iter()
+            .flat_map(|pattern| ["-e".to_string(), pattern.to_string()])
+            .chain(["-x".to_string()])
+            .collect();
+
+        AccessService {
+            runner: Runner::new(
+                Some(RunnerType::Dmenu(Command {
+                    command: "grep".to_string(),
+                    arguments: Some(arguments),
+                    ..Default::default()
+                })),
+                None,
+            ),
+        }
+    }
+
+    fn handle() -> ObjectPath<'static> {
+        ObjectPath::from_static_str("/org/freedesktop/portal/desktop/request/1_1/t").unwrap()
+    }
+
+    fn choices() -> Value<'static> {
+        Value::from(vec![
+            (
+                "encoding",
+                "Encoding",
+                vec![("utf8", "UTF-8"), ("latin1", "Latin-1")],
+                "latin1",
+            ),
+            ("remember", "Remember", vec![], "true"),
+        ])
+    }
+
+    async fn access_dialog(
+        service: &AccessService,
+    ) -> (u32, HashMap<String, zvariant::OwnedValue>) {
+        let options = HashMap::from([("choices", choices())]);
+
+        service
+            .access_dialog(
+                handle(),
+                "org.example.App",
+                "",
+                "Open file",
+                "",
+                "",
+                options,
+            )
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn the_initial_option_is_offered_first() {
+        let Value::Array(choices) = choices() else {
+            unreachable!();
+        };
+        let choices: Vec<Choice> = choices.iter().filter_map(Choice::from_value).collect();
+
+        let ids = |choice: &Choice| -> Vec<String> {
+            choice.picks().into_iter().map(|(id, _)| id).collect()
+        };
+
+        assert_eq!(ids(&choices[0]), ["latin1", "utf8"]);
+        assert_eq!(ids(&choices[1]), ["true", "false"]);
+    }
+
+    #[tokio::test]
+    async fn granting_returns_the_selected_choices() {
+        let service = service(&["Open file: Grant Access", "Encoding: UTF-8", "Remember: no"]);
+
+        let (response, results) = access_dialog(&service).await;
+
+        assert_eq!(response, 0);
+        assert_eq!(
+            results.get("choices").map(|choices| &**choices),
+            Some(&Value::from(vec![
+                ("encoding".to_string(), "utf8".to_string()),
+                ("remember".to_string(), "false".to_string()),
+            ]))
+        );
+    }
+
+    #[tokio::test]
+    async fn denying_or_cancelling_is_response_1() {
+        for patterns in [
+            &["Open file: Deny Access"][..],
+            &["nothing"][..],
+            &["Open file: Grant Access"][..],
+        ] {
+            let (response, results) = access_dialog(&service(patterns)).await;
+
+            assert_eq!(response, 1, "{:?} was not denied", patterns);
+            assert!(results.is_empty());
+        }
+    }
+}
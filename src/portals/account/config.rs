@@ -0,0 +1,30 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::portals::{
+    appchooser::config::RunnerType,
+    dialog::{is_auto_approved, validate_runner},
+};
+
+#[derive(Debug, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "kebab-case")]
+#[schemars(deny_unknown_fields)]
+pub struct AccountConfig {
+    pub enabled: bool,
+    pub runner: Option<RunnerType>,
+    pub auto_approve: Option<Vec<String>>,
+}
+
+impl AccountConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        problems.extend(validate_runner("account.runner", self.runner.as_ref()));
+
+        problems
+    }
+
+    pub fn is_auto_approved(&self, app_id: &str) -> bool {
+        is_auto_approved(self.auto_approve.as_ref(), app_id)
+    }
+}
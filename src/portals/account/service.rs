@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use zbus::{fdo, interface};
+use zvariant::{ObjectPath, OwnedValue, Value};
+
+use crate::{portals::dialog::Runner, utils::uri::path_to_file_uri};
+
+use super::{config::AccountConfig, user::User};
+
+pub struct AccountService {
+    pub config: AccountConfig,
+    pub runner: Runner,
+}
+
+#[interface(name = "org.freedesktop.impl.portal.Account")]
+impl AccountService {
+    #[zbus(property, name = "version")]
+    async fn version(&self) -> u32 {
+        1
+    }
+
+    async fn get_user_information(
+        &self,
+        handle: ObjectPath<'_>,
+        app_id: &str,
+        window: &str,
+        options: HashMap<&str, Value<'_>>,
+    ) -> fdo::Result<(u32, HashMap<String, OwnedValue>)> {
+        tracing::debug!(
+            "GetUserInformation called with handle: {:?}, app_id: {:?}, window: {}, options: {:?}",
+            handle,
+            app_id,
+            window,
+            options
+        );
+
+        if !self.config.is_auto_approved(app_id) {
+            let reason = match options.get("reason") {
+                Some(Value::Str(reason)) if !reason.is_empty() => format!(" ({})", reason),
+                _ => String::new(),
+            };
+
+            let app = if app_id.is_empty() {
+                "this application"
+            } else {
+                app_id
+            };
+
+            let question = format!("Share your name with {}{}", app, reason);
+
+            if !self.runner.confirm(&question).await? {
+                tracing::info!("Sharing user information with {:?} was denied", app_id);
+                return Ok((1, HashMap::new()));
+            }
+        }
+
+        let Some(user) = User::current() else {
+            return Err(fdo::Error::Failed(
+                "zenzai: could not read user information".to_string(),
+            ));
+        };
+
+        let image = user
+            .image()
+            .map(|path| path_to_file_uri(&path))
+            .unwrap_or_default();
+
+        tracing::info!("Sharing user information {:?} with {:?}", user, app_id);
+
+        let mut m = HashMap::new();
+        m.insert("id".to_string(), zvariant::Str::from(user.id).into());
+        m.insert("name".to_string(), zvariant::Str::from(user.name).into());
+        m.insert("image".to_string(), zvariant::Str::from(image).into());
+
+        Ok((0, m))
+    }
+}
@@ -0,0 +1,73 @@
+use std::{ffi::CStr, path::PathBuf};
+
+use ini::Ini;
+
+#[derive(Debug)]
+pub struct User {
+    pub id: String,
+    pub name: String,
+    pub home: PathBuf,
+}
+
+impl User {
+    pub fn current() -> Option<User> {
+        let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut result: *mut libc::passwd = std::ptr::null_mut();
+        let mut buffer = vec![0 as libc::c_char; 4096];
+
+        let ret = unsafe {
+            libc::getpwuid_r(
+                libc::getuid(),
+                &mut passwd,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut result,
+            )
+        };
+
+        if ret != 0 || result.is_null() {
+            return None;
+        }
+
+        let field = |ptr: *const libc::c_char| {
+            if ptr.is_null() {
+                String::new()
+            } else {
+                unsafe { CStr::from_ptr(ptr) }.to_string_lossy().to_string()
+            }
+        };
+
+        let id = field(passwd.pw_name);
+
+        // GECOS is "Full Name,Room,Phone,..."
+        let name = field(passwd.pw_gecos)
+            .split(',')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+
+        Some(User {
+            name: if name.is_empty() { id.clone() } else { name },
+            id,
+            home: field(passwd.pw_dir).into(),
+        })
+    }
+
+    // ~/.face is what display managers use, AccountsService keeps its own copy
+    pub fn image(&self) -> Option<PathBuf> {
+        let accounts_service =
+            Ini::load_from_file(format!("/var/lib/AccountsService/users/{}", self.id))
+                .ok()
+                .and_then(|ini| ini.get_from(Some("User"), "Icon").map(PathBuf::from));
+
+        [
+            Some(self.home.join(".face")),
+            Some(self.home.join(".face.icon")),
+            accounts_service,
+            Some(PathBuf::from("/var/lib/AccountsService/icons").join(&self.id)),
+        ]
+        .into_iter()
+        .flatten()
+        .find(|path| path.is_file())
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use zbus::{Connection, proxy};
+use zvariant::Value;
+
+#[proxy(interface = "org.freedesktop.Application")]
+trait Application {
+    fn activate(&self, platform_data: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+
+    fn open(&self, uris: &[&str], platform_data: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+}
+
+// the object path is derived from the desktop id, e.g. org.example.App -> /org/example/App
+fn object_path(app_id: &str) -> String {
+    format!("/{}", app_id.replace('.', "/").replace('-', "_"))
+}
+
+pub async fn open(
+    conn: &Connection,
+    app_id: &str,
+    uris: &[String],
+    token: Option<&str>,
+) -> zbus::Result<()> {
+    let proxy = ApplicationProxy::builder(conn)
+        .destination(app_id.to_string())?
+        .path(object_path(app_id))?
+        .build()
+        .await?;
+
+    let mut platform_data = HashMap::new();
+    if let Some(token) = token {
+        platform_data.insert("activation-token", Value::from(token));
+        platform_data.insert("desktop-startup-id", Value::from(token));
+    }
+
+    if uris.is_empty() {
+        return proxy.activate(platform_data).await;
+    }
+
+    let uris: Vec<&str> = uris.iter().map(|uri| uri.as_str()).collect();
+    proxy.open(&uris, platform_data).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use zbus::interface;
+    use zvariant::OwnedValue;
+
+    use super::open;
+    use crate::utils::testing::p2p_pair;
+
+    // (method, uris, activation token) of every call
+    type Calls = Arc<Mutex<Vec<(String, Vec<String>, Option<String>)>>>;
+
+    #[derive(Default)]
+    struct MockApplication {
+        calls: Calls,
+    }
+
+    impl MockApplication {
+        fn record(
+            &self,
+            method: &str,
+            uris: Vec<String>,
+            platform_data: HashMap<String, OwnedValue>,
+        ) {
+            let token = platform_data
+                .get("activation-token")
+                .and_then(|token| String::try_from(token.try_clone().ok()?).ok());
+
+            self.calls
+                .lock()
+                .unwrap()
+                .push((method.to_string(), uris, token));
+        }
+    }
+
+    #[interface(name = "org.freedesktop.Application")]
+    impl MockApplication {
+        fn activate(&self, platform_data: HashMap<String, OwnedValue>) {
+            self.record("Activate", Vec::new(), platform_data);
+        }
+
+        fn open(&self, uris: Vec<String>, platform_data: HashMap<String, OwnedValue>) {
+            self.record("Open", uris, platform_data);
+        }
+    }
+
+    #[tokio::test]
+    async fn uris_are_opened_at_the_path_of_the_desktop_id() {
+        let (app, portal) = p2p_pair().await;
+        let mock = MockApplication::default();
+        let calls = mock.calls.clone();
+        app.object_server()
+            .at("/org/example/My_App", mock)
+            .await
+            .unwrap();
+
+        let uri = "file:///home/user/a.txt".to_string();
+
+        open(
+            &portal,
+            "org.example.My-App",
+            std::slice::from_ref(&uri),
+            Some("token"),
+        )
+        .await
+        .unwrap();
+        open(&portal, "org.example.My-App", &[], None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                ("Open".to_string(), vec![uri], Some("token".to_string())),
+                ("Activate".to_string(), vec![], None),
+            ]
+        );
+    }
+}
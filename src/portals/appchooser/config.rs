@@ -1,25 +1,113 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path};
 
+use schemars::JsonSchema;
 use serde::Deserialize;
 
-use crate::terminal::{Terminal, command_path};
+use crate::{
+    portals::dialog::validate_runner,
+    terminal::{Terminal, command_path, exec_arguments},
+    utils::command::expand,
+};
 
-#[derive(Debug, Deserialize, Default)]
+use super::{
+    desktop_files::find_desktop_entry,
+    run_command::{Launch, RunCommandError},
+};
+
+#[derive(Debug, Deserialize, JsonSchema, Default)]
 #[serde(rename_all = "kebab-case")]
+#[schemars(deny_unknown_fields)]
 pub struct AppChooserConfig {
     pub enabled: bool,
     pub runner: Option<RunnerType>,
+    pub launch_mode: Option<LaunchMode>,
+    pub launch_check_ms: Option<u64>,
     pub defaults: HashMap<String, DefaultMapping>,
 }
 
-#[derive(Debug, Deserialize)]
+impl AppChooserConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        problems.extend(validate_runner("appchooser.runner", self.runner.as_ref()));
+
+        if self.enabled && self.runner.is_none() {
+            problems.push("appchooser.runner: runner is unset".to_string());
+        }
+
+        let mut defaults: Vec<(&String, &DefaultMapping)> = self.defaults.iter().collect();
+        defaults.sort_by_key(|(content_type, _)| *content_type);
+
+        for (content_type, mapping) in defaults {
+            if !is_valid_content_type(content_type) {
+                problems.push(format!(
+                    "appchooser.defaults.{:?}: invalid content type",
+                    content_type
+                ));
+            }
+
+            let files = match mapping {
+                DefaultMapping::DesktopFile(file) => vec![file.clone()],
+                DefaultMapping::DesktopFileChoice(files) => files.clone(),
+                DefaultMapping::Command(_) | DefaultMapping::CommandChoice(_) => vec![],
+            };
+
+            for file in files {
+                if find_desktop_entry(&file).is_none() {
+                    problems.push(format!(
+                        "appchooser.defaults.{:?}: desktop file {:?} could not be found",
+                        content_type, file
+                    ));
+                }
+            }
+        }
+
+        problems
+    }
+}
+
+// type/subtype as described in RFC 6838, wildcards are allowed at the end (e.g. "image/*")
+fn is_valid_content_type(content_type: &str) -> bool {
+    fn is_valid_name(name: &str) -> bool {
+        let name = name.strip_suffix('*').unwrap_or(name);
+
+        name.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$&^_.+-".contains(c))
+    }
+
+    if content_type == "*" {
+        return true;
+    }
+
+    match content_type.split_once('/') {
+        Some((kind, subtype)) => {
+            !kind.is_empty()
+                && !kind.contains('*')
+                && !subtype.is_empty()
+                && is_valid_name(kind)
+                && is_valid_name(subtype)
+        }
+        None => false,
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum LaunchMode {
+    #[default]
+    Spawn,
+    DoubleFork,
+    SystemdScope,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Clone)]
 #[serde(tag = "type")]
 #[serde(rename_all = "kebab-case")]
 pub enum RunnerType {
     Dmenu(Command),
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Clone)]
 #[serde(untagged)]
 pub enum DefaultMapping {
     Command(Command),
@@ -28,43 +116,196 @@ pub enum DefaultMapping {
     DesktopFileChoice(Vec<String>),
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Clone)]
+pub struct InputFile {
+    pub uri: String,
+    pub path: String,
+    pub content_type: String,
+    pub app_id: String,
+}
+
+impl InputFile {
+    pub fn expand(&self, template: &str) -> String {
+        let path = Path::new(&self.path);
+        let basename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let dir = path
+            .parent()
+            .map(|dir| dir.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        expand(
+            template,
+            &[
+                ("{uri}", &self.uri),
+                ("{path}", &self.path),
+                ("{basename}", &basename),
+                ("{dir}", &dir),
+                ("{content_type}", &self.content_type),
+                ("{app_id}", &self.app_id),
+            ],
+        )
+    }
+}
+
+const PLACEHOLDERS: [&str; 6] = [
+    "{uri}",
+    "{path}",
+    "{basename}",
+    "{dir}",
+    "{content_type}",
+    "{app_id}",
+];
+
+fn has_placeholder(arg: &str) -> bool {
+    PLACEHOLDERS
+        .iter()
+        .any(|placeholder| arg.contains(placeholder))
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Clone, Default)]
+#[schemars(deny_unknown_fields)]
 pub struct Command {
     pub command: String,
     pub arguments: Option<Vec<String>>,
+    pub env: Option<HashMap<String, String>>,
+    pub cwd: Option<String>,
+    pub terminal: Option<bool>,
+    pub class: Option<String>,
+    pub title: Option<String>,
 }
 
 impl Command {
-    pub fn with_terminal(&self, terminal: &Terminal) -> Command {
-        let command = command_path(&terminal)
-            .unwrap()
-            .to_str()
-            .expect("could not convert OsStr to &str")
+    pub fn resolve(&self, terminal: Option<&Terminal>) -> Result<Command, RunCommandError> {
+        if self.terminal.unwrap_or(false) {
+            self.with_terminal(terminal)
+        } else {
+            Ok(self.clone())
+        }
+    }
+
+    pub fn with_terminal(&self, terminal: Option<&Terminal>) -> Result<Command, RunCommandError> {
+        let terminal = terminal.ok_or(RunCommandError::NoTerminal)?;
+
+        let command = command_path(terminal)
+            .ok_or(RunCommandError::NoTerminal)?
+            .to_string_lossy()
             .to_string();
 
-        let mut arguments = Vec::new();
-        arguments.push("-e".to_string());
-        arguments.push(self.command.clone());
-        self.arguments
-            .clone()
-            .unwrap_or_default()
-            .iter()
-            .for_each(|arg| arguments.push(arg.clone()));
+        // without an explicit class, window rules can match on the command name (e.g. "btop")
+        let class = self.class.clone().or_else(|| {
+            Path::new(&self.command)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        });
 
-        Command {
+        let arguments = exec_arguments(
+            terminal,
+            &self.command,
+            &self.arguments.clone().unwrap_or_default(),
+            class.as_deref(),
+            self.title.as_deref(),
+        );
+
+        Ok(Command {
             command,
             arguments: Some(arguments),
-        }
+            terminal: Some(false),
+            ..self.clone()
+        })
     }
 
-    pub fn with_input_file(&self, file: String) -> Command {
-        // TODO #8: add support for other ways to supply file paths
+    // configured commands have no desktop id, so their name stands in for it
+    pub fn launch(
+        &self,
+        input: &InputFile,
+        terminal: Option<&Terminal>,
+    ) -> Result<Launch, RunCommandError> {
+        let app_id = Path::new(&self.command)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or(self.command.clone());
+
+        Ok(Launch::Exec {
+            app_id,
+            cmd: self.with_input_file(input).resolve(terminal)?,
+        })
+    }
+
+    pub fn with_input_file(&self, input: &InputFile) -> Command {
         let mut args = self.arguments.clone().unwrap_or_default();
-        args.push(file);
+
+        // without any placeholders the file is passed as the last argument
+        if args.iter().any(|arg| has_placeholder(arg)) {
+            args = args.iter().map(|arg| input.expand(arg)).collect();
+        } else {
+            args.push(input.path.clone());
+        }
+
+        let env = self.env.as_ref().map(|env| {
+            env.iter()
+                .map(|(k, v)| (k.clone(), input.expand(v)))
+                .collect()
+        });
 
         Command {
-            command: self.command.clone(),
             arguments: Some(args),
+            env,
+            cwd: self.cwd.as_ref().map(|cwd| input.expand(cwd)),
+            title: self.title.as_ref().map(|title| input.expand(title)),
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, InputFile};
+    use crate::portals::appchooser::run_command::Launch;
+
+    fn input_file() -> InputFile {
+        InputFile {
+            uri: "file:///home/user/My%20Notes/{dir}.txt".to_string(),
+            path: "/home/user/My Notes/{dir}.txt".to_string(),
+            content_type: "text/plain".to_string(),
+            app_id: "org.example.App".to_string(),
         }
     }
+
+    #[test]
+    fn expand_replaces_every_placeholder() {
+        assert_eq!(
+            input_file().expand("{basename} in {dir} ({content_type}, {app_id})"),
+            "{dir}.txt in /home/user/My Notes (text/plain, org.example.App)"
+        );
+        assert_eq!(
+            input_file().expand("--uri={uri}"),
+            "--uri=file:///home/user/My%20Notes/{dir}.txt"
+        );
+    }
+
+    #[test]
+    fn expand_keeps_unknown_placeholders() {
+        assert_eq!(input_file().expand("{file} {path"), "{file} {path");
+    }
+
+    #[test]
+    fn commands_launch_under_their_name() {
+        let cmd = Command {
+            command: "/usr/bin/nvim".to_string(),
+            ..Default::default()
+        };
+
+        let Ok(Launch::Exec { app_id, cmd }) = cmd.launch(&input_file(), None) else {
+            panic!("expected an exec launch");
+        };
+
+        assert_eq!(app_id, "nvim");
+        assert_eq!(
+            cmd.arguments,
+            Some(vec!["/home/user/My Notes/{dir}.txt".to_string()])
+        );
+    }
 }
@@ -1,36 +1,72 @@
-use std::{env, fs, path::PathBuf};
+use std::{fs, path::PathBuf};
 
 use ini::Ini;
 
-use crate::terminal::Terminal;
+use crate::{terminal::Terminal, utils::xdg};
 
-use super::config::Command;
+use super::{
+    config::{Command, InputFile},
+    run_command::{Launch, RunCommandError},
+};
 
 #[derive(Debug)]
 pub struct DesktopEntry {
+    pub id: String,
     pub name: String,
     pub exec: Command,
     pub is_terminal: bool,
+    pub dbus_activatable: bool,
 }
 
 impl DesktopEntry {
-    pub fn command(&self, terminal: &Terminal) -> Command {
+    pub fn command(
+        &self,
+        input: &InputFile,
+        terminal: Option<&Terminal>,
+    ) -> Result<Command, RunCommandError> {
+        let exec = self.exec.with_input_file(input);
+
         if self.is_terminal {
-            self.exec.with_terminal(terminal)
+            exec.with_terminal(terminal)
+        } else {
+            Ok(exec)
+        }
+    }
+
+    pub fn launch(
+        &self,
+        terminal: Option<&Terminal>,
+        input: &InputFile,
+    ) -> Result<Launch, RunCommandError> {
+        let exec = self.command(input, terminal)?;
+
+        if self.dbus_activatable {
+            Ok(Launch::DBus {
+                app_id: self.id.clone(),
+                uris: vec![input.uri.clone()],
+                exec,
+            })
         } else {
-            self.exec.clone()
+            Ok(Launch::Exec {
+                app_id: self.id.clone(),
+                cmd: exec,
+            })
         }
     }
 }
 
-fn desktop_files() -> Vec<PathBuf> {
-    let xdg_data_dirs =
-        env::var("XDG_DATA_DIRS").unwrap_or("/usr/local/share:/usr/share".to_string());
+fn parse_bool(value: &str) -> bool {
+    matches!(value.to_lowercase().as_str(), "true" | "1")
+}
 
-    xdg_data_dirs
-        .split(':')
-        .filter_map(|s| {
-            let applications_dir = PathBuf::from(s).join("applications");
+// the user's entries come first so they win over the system wide ones, this also
+// covers launchers installed through the DynamicLauncher portal
+fn desktop_files() -> Vec<PathBuf> {
+    [xdg::data_home()]
+        .into_iter()
+        .chain(xdg::data_dirs())
+        .filter_map(|dir| {
+            let applications_dir = dir.join("applications");
 
             if applications_dir.is_dir() {
                 Some(applications_dir)
@@ -60,43 +96,39 @@ fn desktop_files() -> Vec<PathBuf> {
 }
 
 pub fn find_desktop_entry(name: &str) -> Option<DesktopEntry> {
-    let name = name.trim_end_matches(".desktop");
+    let id = name.trim_end_matches(".desktop");
 
     let matching: Vec<PathBuf> = desktop_files()
         .into_iter()
         .filter(|f| f.exists() && f.is_file())
         .filter(|f| {
             f.file_stem()
-                .map(|s| s.to_str().unwrap_or("") == name)
+                .map(|s| s.to_str().unwrap_or("") == id)
                 .unwrap_or(false)
         })
         .collect();
 
-    if matching.len() == 0 {
+    if matching.is_empty() {
         return None;
     }
 
-    let first = matching.get(0).unwrap();
+    let first = matching.first().unwrap();
 
-    let (name, terminal, exec) = Ini::load_from_file(first)
+    let (name, terminal, exec, dbus_activatable, path) = Ini::load_from_file(first)
         .map(|conf| {
             conf.section(Some("Desktop Entry"))
                 .map(|section| {
                     (
                         section.get("Name").map(|s| s.to_string()),
-                        section.get("Terminal").map(|s| {
-                            match s.to_string().to_lowercase().as_str() {
-                                "true" => true,
-                                "1" => true,
-                                _ => false,
-                            }
-                        }),
+                        section.get("Terminal").map(parse_bool),
                         section.get("Exec").map(|s| s.to_string()),
+                        section.get("DBusActivatable").map(parse_bool),
+                        section.get("Path").map(|s| s.to_string()),
                     )
                 })
-                .unwrap_or((None, None, None))
+                .unwrap_or((None, None, None, None, None))
         })
-        .unwrap_or((None, None, None));
+        .unwrap_or((None, None, None, None, None));
 
     if exec.is_none() {
         tracing::error!("Entry: {:?} is invalid (no Exec)", first);
@@ -104,8 +136,10 @@ pub fn find_desktop_entry(name: &str) -> Option<DesktopEntry> {
     }
 
     let exec = exec.unwrap();
+    let id = id.to_string();
     let name = name.unwrap_or(exec.clone());
     let is_terminal = terminal.unwrap_or(false);
+    let dbus_activatable = dbus_activatable.unwrap_or(false);
 
     let exec_parts: Vec<&str> = exec.split(" ").collect();
 
@@ -120,11 +154,62 @@ pub fn find_desktop_entry(name: &str) -> Option<DesktopEntry> {
                 .map(|s| s.to_string())
                 .collect(),
         ),
+        cwd: path.filter(|path| !path.is_empty()),
+        class: Some(id.clone()),
+        ..Default::default()
     };
 
     Some(DesktopEntry {
+        id,
         name,
         exec,
         is_terminal,
+        dbus_activatable,
     })
 }
+
+// mimeapps.list locations in the order of the XDG MIME Applications spec
+fn mimeapps_lists() -> Vec<PathBuf> {
+    let desktops: Vec<String> = xdg::current_desktops()
+        .iter()
+        .map(|desktop| desktop.to_lowercase())
+        .collect();
+
+    let config_dirs = [xdg::config_home()].into_iter().chain(xdg::config_dirs());
+    let data_dirs = [xdg::data_home()]
+        .into_iter()
+        .chain(xdg::data_dirs())
+        .map(|dir| dir.join("applications"));
+
+    config_dirs
+        .chain(data_dirs)
+        .flat_map(|dir| {
+            desktops
+                .iter()
+                .map(|desktop| format!("{}-mimeapps.list", desktop))
+                .chain(["mimeapps.list".to_string()])
+                .map(move |name| dir.join(name))
+                .collect::<Vec<PathBuf>>()
+        })
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+// the desktop entry set as default application for the content type in mimeapps.list
+pub fn default_handler(content_type: &str) -> Option<DesktopEntry> {
+    mimeapps_lists()
+        .iter()
+        .filter_map(|path| Ini::load_from_file(path).ok())
+        .filter_map(|ini| {
+            ini.get_from(Some("Default Applications"), content_type)
+                .map(|value| value.to_string())
+        })
+        .flat_map(|value| {
+            value
+                .split(';')
+                .filter(|name| !name.is_empty())
+                .map(|name| name.to_string())
+                .collect::<Vec<String>>()
+        })
+        .find_map(|name| find_desktop_entry(&name))
+}
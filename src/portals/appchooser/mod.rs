@@ -1,4 +1,7 @@
+mod application;
 pub mod config;
-mod desktop_files;
-mod run_command;
+pub mod desktop_files;
+pub mod run_command;
 pub mod service;
+mod supervisor;
+mod systemd;
@@ -1,15 +1,44 @@
-use std::string::FromUtf8Error;
+use std::{process::ExitStatus, string::FromUtf8Error, time::Duration};
 
 use tokio::io::AsyncWriteExt;
-use zbus::fdo;
+use zbus::{Connection, fdo};
 
-use super::config::Command;
+use super::{
+    application,
+    config::{Command, LaunchMode},
+    supervisor::supervise,
+    systemd,
+};
+
+#[derive(Debug, Clone)]
+pub enum Launch {
+    Exec {
+        app_id: String,
+        cmd: Command,
+    },
+    DBus {
+        app_id: String,
+        uris: Vec<String>,
+        exec: Command,
+    },
+}
+
+impl Launch {
+    pub fn app_id(&self) -> &str {
+        match self {
+            Launch::Exec { app_id, .. } | Launch::DBus { app_id, .. } => app_id,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub enum RunCommandError {
     IOError(std::io::Error),
     FailedToOpenStdin,
     Utf8Error(FromUtf8Error),
+    ExitedEarly(ExitStatus),
+    NoTerminal,
+    NothingSelected,
     Other(String),
 }
 
@@ -23,6 +52,15 @@ impl From<RunCommandError> for fdo::Error {
             RunCommandError::Utf8Error(err) => {
                 fdo::Error::Failed(format!("could not convert to utf-8 {:?}", err))
             }
+            RunCommandError::ExitedEarly(status) => {
+                fdo::Error::Failed(format!("application failed to launch: {}", status))
+            }
+            RunCommandError::NoTerminal => {
+                fdo::Error::Failed("no terminal available to run this application".to_string())
+            }
+            RunCommandError::NothingSelected => {
+                fdo::Error::Failed("no application was selected".to_string())
+            }
             RunCommandError::Other(err) => fdo::Error::Failed(err),
         }
     }
@@ -40,20 +78,126 @@ impl From<FromUtf8Error> for RunCommandError {
     }
 }
 
-pub async fn run_command(cmd: &Command) -> Result<(), RunCommandError> {
-    tracing::info!("Run Command: {:?}", cmd);
-    let _ = tokio::process::Command::new(&cmd.command)
-        .args(&cmd.arguments.clone().unwrap_or_default())
-        .spawn()?;
+pub async fn run_command(
+    conn: &Connection,
+    launch: &Launch,
+    token: Option<&str>,
+    mode: LaunchMode,
+    grace_period: Duration,
+) -> Result<(), RunCommandError> {
+    match launch {
+        Launch::Exec { app_id, cmd } => {
+            spawn_command(conn, cmd, app_id, token, mode, grace_period).await
+        }
+        Launch::DBus { app_id, uris, exec } => {
+            tracing::info!("Activate Application: {} {:?}", app_id, uris);
+
+            match application::open(conn, app_id, uris, token).await {
+                Ok(()) => Ok(()),
+                Err(err) => {
+                    tracing::warn!(
+                        "Could not activate {} via D-Bus, falling back to Exec: {:?}",
+                        app_id,
+                        err
+                    );
+                    spawn_command(conn, exec, app_id, token, mode, grace_period).await
+                }
+            }
+        }
+    }
+}
+
+async fn spawn_command(
+    conn: &Connection,
+    cmd: &Command,
+    app_id: &str,
+    token: Option<&str>,
+    mode: LaunchMode,
+    grace_period: Duration,
+) -> Result<(), RunCommandError> {
+    tracing::info!("Run Command ({:?}): {:?}", mode, cmd);
+    let mut c = process_command(cmd);
+
+    // hand the activation token over so the compositor can focus the new window
+    if let Some(token) = token {
+        c.env("XDG_ACTIVATION_TOKEN", token)
+            .env("DESKTOP_STARTUP_ID", token);
+    } else {
+        c.env_remove("XDG_ACTIVATION_TOKEN")
+            .env_remove("DESKTOP_STARTUP_ID");
+    }
+
+    match mode {
+        LaunchMode::Spawn => supervise(c.spawn()?, app_id, grace_period).await,
+        LaunchMode::DoubleFork => double_fork(c, grace_period).await,
+        LaunchMode::SystemdScope => {
+            if !systemd::is_available(conn).await {
+                tracing::warn!("systemd user manager is unavailable, falling back to double fork");
+                return double_fork(c, grace_period).await;
+            }
+
+            let child = c.spawn()?;
+
+            if let Some(pid) = child.id() {
+                match systemd::start_scope(conn, app_id, pid).await {
+                    Ok(scope) => tracing::info!("Moved {} ({}) into {}", app_id, pid, scope),
+                    Err(err) => tracing::warn!(
+                        "Could not move {} ({}) into a transient scope: {:?}",
+                        app_id,
+                        pid,
+                        err
+                    ),
+                }
+            }
+
+            supervise(child, app_id, grace_period).await
+        }
+    }
+}
+
+fn process_command(cmd: &Command) -> tokio::process::Command {
+    let mut c = tokio::process::Command::new(&cmd.command);
+    c.args(cmd.arguments.clone().unwrap_or_default());
+    c.envs(cmd.env.clone().unwrap_or_default());
+
+    if let Some(cwd) = &cmd.cwd {
+        c.current_dir(cwd);
+    }
+
+    c
+}
+
+// detaches the command from the portal by forking twice, the intermediate process exits
+// right away so the application gets reparented and never becomes our zombie. That also means
+// it can't be watched for early failures
+async fn double_fork(
+    mut c: tokio::process::Command,
+    grace_period: Duration,
+) -> Result<(), RunCommandError> {
+    if !grace_period.is_zero() {
+        tracing::warn!("launch-check-ms is ignored, double forked apps can't be watched");
+    }
+
+    unsafe {
+        c.pre_exec(|| match libc::fork() {
+            -1 => Err(std::io::Error::last_os_error()),
+            0 => {
+                libc::setsid();
+                Ok(())
+            }
+            _ => libc::_exit(0),
+        });
+    }
+
+    let _ = c.spawn()?.wait().await?;
     Ok(())
 }
 
 pub async fn run_picker_command(
     cmd: &Command,
-    options: &Vec<String>,
+    options: &[String],
 ) -> Result<String, RunCommandError> {
-    let mut c = tokio::process::Command::new(&cmd.command)
-        .args(&cmd.arguments.clone().unwrap_or_default())
+    let mut c = process_command(cmd)
         .stdin(std::process::Stdio::piped())
         .stdout(std::process::Stdio::piped())
         .spawn()?;
@@ -76,5 +220,59 @@ pub async fn run_picker_command(
 
     let stdout = String::from_utf8(output.stdout)?;
 
+    // dmenu style runners print nothing when the selection was cancelled
+    if stdout.trim().is_empty() {
+        return Err(RunCommandError::NothingSelected);
+    }
+
     Ok(stdout)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, time::Duration};
+
+    use super::{Launch, run_command};
+    use crate::{
+        portals::appchooser::config::{Command, LaunchMode},
+        utils::testing::p2p_pair,
+    };
+
+    #[tokio::test]
+    async fn apps_that_cannot_be_activated_are_executed() {
+        // the other end serves no objects, so activating via D-Bus fails
+        let (app, portal) = p2p_pair().await;
+        let _server = app.object_server();
+
+        let output = std::env::temp_dir().join(format!(
+            "zenzai-run-command-test-{:08x}",
+            rand::random::<u32>()
+        ));
+
+        let launch = Launch::DBus {
+            app_id: "org.example.App".to_string(),
+            uris: vec!["file:///home/user/a.txt".to_string()],
+            exec: Command {
+                command: "sh".to_string(),
+                arguments: Some(vec![
+                    "-c".to_string(),
+                    format!("echo \"$XDG_ACTIVATION_TOKEN\" > {}", output.display()),
+                ]),
+                ..Default::default()
+            },
+        };
+
+        run_command(
+            &portal,
+            &launch,
+            Some("token"),
+            LaunchMode::Spawn,
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(&output).unwrap(), "token\n");
+        fs::remove_file(output).unwrap();
+    }
+}
@@ -1,6 +1,7 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 use zbus::{
+    Connection,
     fdo::{self},
     interface,
 };
@@ -9,16 +10,16 @@ use zvariant::{ObjectPath, OwnedValue, Value};
 use crate::{
     portals::appchooser::{
         desktop_files::{DesktopEntry, find_desktop_entry},
-        run_command::{RunCommandError, run_command, run_picker_command},
+        run_command::{Launch, RunCommandError, run_command, run_picker_command},
     },
     terminal::Terminal,
-    utils::hashmap::wildcard_get,
+    utils::{hashmap::wildcard_get, uri::file_uri_to_path},
 };
 
-use super::config::{AppChooserConfig, Command, DefaultMapping, RunnerType};
+use super::config::{AppChooserConfig, DefaultMapping, InputFile, RunnerType};
 
 pub struct AppChooserService {
-    pub terminal: Terminal,
+    pub terminal: Option<Terminal>,
     pub config: AppChooserConfig,
 }
 
@@ -26,6 +27,7 @@ pub struct AppChooserService {
 impl AppChooserService {
     async fn choose_application(
         &self,
+        #[zbus(connection)] conn: &Connection,
         handle: ObjectPath<'_>,
         app_id: &str,
         parent_window: &str,
@@ -51,7 +53,13 @@ impl AppChooserService {
 
         let uri = options.get("uri");
         let content_type = options.get("content_type");
-        let activation_token = options.get("activation_token").map(|s| s.to_string());
+        let activation_token = options
+            .get("activation_token")
+            .and_then(|token| match token {
+                Value::Str(token) => Some(token.to_string()),
+                _ => None,
+            })
+            .filter(|token| !token.is_empty());
 
         if uri.is_none() || content_type.is_none() {
             tracing::error!("uri or content_type undefined {:?}", &options);
@@ -64,17 +72,22 @@ impl AppChooserService {
         let uri = uri
             .map(|uri| uri.to_string())
             .map(|uri| uri.trim_matches('"').to_string())
-            .map(|uri| uri.trim_start_matches("file://").to_string())
             .unwrap();
         let content_type = content_type.unwrap().to_string();
         let content_type = content_type.trim_matches('"');
 
-        let runner_cmd = match &runner_type {
-            RunnerType::Dmenu(cmd) => cmd,
+        let input = InputFile {
+            path: file_uri_to_path(&uri)
+                .map(|path| path.to_string_lossy().to_string())
+                .unwrap_or(uri.clone()),
+            uri: uri.clone(),
+            content_type: content_type.to_string(),
+            app_id: app_id.to_string(),
         };
 
-        let new_token =
-            activation_token.unwrap_or_else(|| format!("token-{}", rand::random::<u32>()));
+        let RunnerType::Dmenu(runner_cmd) = &runner_type;
+        let launch_mode = self.config.launch_mode.unwrap_or_default();
+        let grace_period = Duration::from_millis(self.config.launch_check_ms.unwrap_or_default());
 
         tracing::info!("URI: {}, Content-Type: {}", uri, content_type);
 
@@ -84,69 +97,24 @@ impl AppChooserService {
         if let Some((_, option)) = wildcard_get(&self.config.defaults, content_type.to_string()) {
             tracing::info!("Selected mapping: {:?}", option);
 
-            let res = match option {
-                DefaultMapping::Command(ref cmd) => {
-                    let cmd = cmd.with_input_file(uri);
-                    Ok(cmd.clone())
-                }
-                DefaultMapping::CommandChoice(ref cmds) => {
-                    let cmds_str: Vec<String> = cmds
-                        .clone()
-                        .into_iter()
-                        .map(|c| c.command.clone())
-                        .collect();
-
-                    tracing::info!("{:?}", cmds_str);
-
-                    run_picker_command(runner_cmd, &cmds_str)
-                        .await
-                        .map(|cmd| {
-                            cmds.into_iter()
-                                .find(|c| c.command == cmd.trim())
-                                .expect("could not find command from options")
-                        })
-                        .map(|cmd| cmd.with_input_file(uri))
-                }
-                DefaultMapping::DesktopFile(ref file) => find_desktop_entry(&file)
-                    .map(|entry| Ok(entry.command(&self.terminal).with_input_file(uri)))
-                    .unwrap_or(Err(RunCommandError::Other(format!(
-                        "Could not find desktop entry for {:?}",
-                        file
-                    )))),
-                DefaultMapping::DesktopFileChoice(ref files) => {
-                    let desktop_entries: Vec<DesktopEntry> = files
-                        .iter()
-                        .filter_map(|name| find_desktop_entry(name))
-                        .collect();
+            let res =
+                resolve_mapping(&option, &input, self.terminal.as_ref(), Some(runner_type)).await;
 
-                    let options = desktop_entries
-                        .iter()
-                        .map(|entry| entry.name.clone())
-                        .collect();
-
-                    run_picker_command(runner_cmd, &options)
-                        .await
-                        .map(|entry| {
-                            desktop_entries
-                                .iter()
-                                .find(|e| e.name == entry.trim())
-                                .expect("could not find desktop file from options")
-                        })
-                        .map(|entry| entry.command(&self.terminal).with_input_file(uri))
-                }
-            };
-
-            if res.is_err() {
-                let err = format!("something went wrong while running {:?}", option);
-                tracing::error!("{}", err);
-                return Err(fdo::Error::Failed(err));
-            }
-
-            let res = res.unwrap();
-
-            let _ = run_command(&res).await?;
-
-            return cmd_ok(&res, &new_token);
+            let res = res.map_err(|err| {
+                tracing::error!("something went wrong while running {:?}: {:?}", option, err);
+                fdo::Error::from(err)
+            })?;
+
+            run_command(
+                conn,
+                &res,
+                activation_token.as_deref(),
+                launch_mode,
+                grace_period,
+            )
+            .await?;
+
+            return cmd_ok(&res, activation_token.as_deref());
         } else {
             tracing::warn!(
                 "No default found for {:?}. Defaults: {:?}",
@@ -167,55 +135,123 @@ impl AppChooserService {
             .filter_map(|name| find_desktop_entry(name))
             .collect();
 
-        let options = desktop_entries
+        let options: Vec<String> = desktop_entries
             .iter()
             .map(|entry| entry.name.clone())
             .collect();
 
         let res = if desktop_entries.len() == 1 {
-            Ok(desktop_entries
+            desktop_entries
                 .first()
                 .unwrap()
-                .command(&self.terminal)
-                .with_input_file(uri))
+                .launch(self.terminal.as_ref(), &input)
         } else {
             run_picker_command(runner_cmd, &options)
                 .await
-                .map(|entry| {
+                .and_then(|entry| {
                     desktop_entries
                         .iter()
                         .find(|e| e.name == entry.trim())
-                        .expect("could not find desktop file from options")
+                        .ok_or(RunCommandError::NothingSelected)
                 })
-                .map(|entry| entry.command(&self.terminal).with_input_file(uri))
+                .and_then(|entry| entry.launch(self.terminal.as_ref(), &input))
         };
 
-        if res.is_err() {
-            let err = format!("something went wrong while running {:?}", options);
-            tracing::error!("{}", err);
-            return Err(fdo::Error::Failed(err));
-        }
-
-        let res = res.unwrap();
-
-        let _ = run_command(&res).await?;
+        let res = res.map_err(|err| {
+            tracing::error!(
+                "something went wrong while running {:?}: {:?}",
+                options,
+                err
+            );
+            fdo::Error::from(err)
+        })?;
+
+        run_command(
+            conn,
+            &res,
+            activation_token.as_deref(),
+            launch_mode,
+            grace_period,
+        )
+        .await?;
+
+        cmd_ok(&res, activation_token.as_deref())
+    }
+}
 
-        cmd_ok(&res, &new_token)
+// turns a configured default mapping into something we can launch, asking via the runner if
+// the mapping is a list of choices
+pub async fn resolve_mapping(
+    mapping: &DefaultMapping,
+    input: &InputFile,
+    terminal: Option<&Terminal>,
+    runner_type: Option<&RunnerType>,
+) -> Result<Launch, RunCommandError> {
+    let runner_cmd = || match runner_type {
+        Some(RunnerType::Dmenu(cmd)) => Ok(cmd),
+        None => Err(RunCommandError::Other("runner type is unset".to_string())),
+    };
+
+    match mapping {
+        DefaultMapping::Command(cmd) => cmd.launch(input, terminal),
+        DefaultMapping::CommandChoice(cmds) => {
+            let cmds_str: Vec<String> = cmds.iter().map(|c| c.command.clone()).collect();
+
+            tracing::info!("{:?}", cmds_str);
+
+            run_picker_command(runner_cmd()?, &cmds_str)
+                .await
+                .and_then(|cmd| {
+                    cmds.iter()
+                        .find(|c| c.command == cmd.trim())
+                        .ok_or(RunCommandError::NothingSelected)
+                })
+                .and_then(|cmd| cmd.launch(input, terminal))
+        }
+        DefaultMapping::DesktopFile(name) => find_desktop_entry(name)
+            .map(|entry| entry.launch(terminal, input))
+            .unwrap_or(Err(RunCommandError::Other(format!(
+                "Could not find desktop entry for {:?}",
+                name
+            )))),
+        DefaultMapping::DesktopFileChoice(files) => {
+            let desktop_entries: Vec<DesktopEntry> = files
+                .iter()
+                .filter_map(|name| find_desktop_entry(name))
+                .collect();
+
+            let options: Vec<String> = desktop_entries
+                .iter()
+                .map(|entry| entry.name.clone())
+                .collect();
+
+            run_picker_command(runner_cmd()?, &options)
+                .await
+                .and_then(|entry| {
+                    desktop_entries
+                        .iter()
+                        .find(|e| e.name == entry.trim())
+                        .ok_or(RunCommandError::NothingSelected)
+                })
+                .and_then(|entry| entry.launch(terminal, input))
+        }
     }
 }
 
-fn cmd_ok(command: &Command, token: &String) -> fdo::Result<(u32, HashMap<String, OwnedValue>)> {
+fn cmd_ok(launch: &Launch, token: Option<&str>) -> fdo::Result<(u32, HashMap<String, OwnedValue>)> {
     let mut m = HashMap::new();
 
     m.insert(
         "app_id".to_string(),
-        zvariant::Str::from(&command.command).into(),
+        zvariant::Str::from(launch.app_id()).into(),
     );
 
-    m.insert(
-        "activation_token".to_string(),
-        zvariant::Str::from(token).into(),
-    );
+    if let Some(token) = token {
+        m.insert(
+            "activation_token".to_string(),
+            zvariant::Str::from(token).into(),
+        );
+    }
 
     Ok((0, m))
 }
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use tokio::process::Child;
+
+use super::run_command::RunCommandError;
+
+// waits for the child in the background so it never lingers as a zombie, if a grace period
+// is set we wait that long first to report applications that fail right away (e.g. exit code 127)
+pub async fn supervise(
+    mut child: Child,
+    name: &str,
+    grace_period: Duration,
+) -> Result<(), RunCommandError> {
+    let pid = child.id().unwrap_or_default();
+
+    if !grace_period.is_zero() {
+        match tokio::time::timeout(grace_period, child.wait()).await {
+            Ok(Ok(status)) if status.success() => {
+                tracing::info!("{} ({}) exited: {}", name, pid, status);
+                return Ok(());
+            }
+            Ok(Ok(status)) => {
+                tracing::error!("{} ({}) failed to launch: {}", name, pid, status);
+                return Err(RunCommandError::ExitedEarly(status));
+            }
+            Ok(Err(err)) => return Err(err.into()),
+            Err(_) => tracing::debug!("{} ({}) is still running", name, pid),
+        }
+    }
+
+    let name = name.to_string();
+
+    tokio::spawn(async move {
+        match child.wait().await {
+            Ok(status) if status.success() => {
+                tracing::info!("{} ({}) exited: {}", name, pid, status)
+            }
+            Ok(status) => tracing::warn!("{} ({}) exited: {}", name, pid, status),
+            Err(err) => tracing::error!("Could not wait for {} ({}): {:?}", name, pid, err),
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use std::path::Path;
+
+use zbus::{Connection, proxy};
+use zvariant::{OwnedObjectPath, Value};
+
+#[proxy(
+    interface = "org.freedesktop.systemd1.Manager",
+    default_service = "org.freedesktop.systemd1",
+    default_path = "/org/freedesktop/systemd1"
+)]
+trait Manager {
+    fn start_transient_unit(
+        &self,
+        name: &str,
+        mode: &str,
+        properties: &[(&str, Value<'_>)],
+        aux: &[(&str, &[(&str, Value<'_>)])],
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(property)]
+    fn version(&self) -> zbus::Result<String>;
+}
+
+// escapes a string the way systemd-escape does, e.g. "-" becomes "\x2d"
+fn escape(value: &str) -> String {
+    value
+        .bytes()
+        .enumerate()
+        .map(|(i, b)| match b {
+            b'.' if i == 0 => format!("\\x{:02x}", b),
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_' | b'.' | b':' => (b as char).to_string(),
+            _ => format!("\\x{:02x}", b),
+        })
+        .collect()
+}
+
+// follows the XDG cgroup naming convention: app-<launcher>-<id>-<random>.scope
+fn scope_name(app_id: &str) -> String {
+    let app_id = Path::new(app_id)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(app_id)
+        .trim_end_matches(".desktop");
+
+    format!(
+        "app-zenzai-{}-{:08x}.scope",
+        escape(app_id),
+        rand::random::<u32>()
+    )
+}
+
+pub async fn is_available(conn: &Connection) -> bool {
+    match ManagerProxy::new(conn).await {
+        Ok(proxy) => proxy.version().await.is_ok(),
+        Err(_) => false,
+    }
+}
+
+pub async fn start_scope(conn: &Connection, app_id: &str, pid: u32) -> zbus::Result<String> {
+    let proxy = ManagerProxy::new(conn).await?;
+
+    let name = scope_name(app_id);
+    let description = format!(
+        "Application launched by xdg-desktop-portal-zenzai: {}",
+        app_id
+    );
+
+    let properties = [
+        ("Description", Value::from(description.as_str())),
+        ("PIDs", Value::from(vec![pid])),
+        ("CollectMode", Value::from("inactive-or-failed")),
+    ];
+
+    proxy
+        .start_transient_unit(&name, "fail", &properties, &[])
+        .await?;
+
+    Ok(name)
+}
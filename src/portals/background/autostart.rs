@@ -0,0 +1,186 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::utils::xdg;
+
+const AUTOSTART_FLAG_DBUS_ACTIVATABLE: u32 = 1;
+
+fn path(app_id: &str) -> io::Result<PathBuf> {
+    if app_id.is_empty() || app_id.contains('/') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid app id {:?}", app_id),
+        ));
+    }
+
+    Ok(xdg::config_home()
+        .join("autostart")
+        .join(format!("{}.desktop", app_id)))
+}
+
+// the escape sequences of string values, applied after quoting the Exec arguments
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+// quotes an argument for the Exec key as described in the desktop entry spec, the result
+// still needs to be escaped like any other string value
+fn quote(arg: &str) -> String {
+    let arg = arg.replace('%', "%%");
+
+    if !arg.is_empty()
+        && !arg.chars().any(|c| {
+            c.is_whitespace()
+                || matches!(
+                    c,
+                    '"' | '\''
+                        | '\\'
+                        | '>'
+                        | '<'
+                        | '~'
+                        | '|'
+                        | '&'
+                        | ';'
+                        | '$'
+                        | '*'
+                        | '?'
+                        | '#'
+                        | '('
+                        | ')'
+                        | '`'
+                )
+        })
+    {
+        return arg;
+    }
+
+    let mut quoted = String::from("\"");
+
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+// the frontend runs flatpak apps via `flatpak run`
+fn is_flatpak(commandline: &[String]) -> bool {
+    match commandline {
+        [flatpak, run, ..] => {
+            Path::new(flatpak)
+                .file_name()
+                .is_some_and(|name| name == "flatpak")
+                && run == "run"
+        }
+        _ => false,
+    }
+}
+
+fn desktop_entry(app_id: &str, commandline: &[String], flags: u32) -> String {
+    let exec = commandline
+        .iter()
+        .map(|arg| quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut entry = format!(
+        "[Desktop Entry]\nType=Application\nName={}\nExec={}\n",
+        escape(app_id),
+        escape(&exec)
+    );
+
+    if is_flatpak(commandline) {
+        entry.push_str(&format!("X-Flatpak={}\n", app_id));
+    }
+
+    if flags & AUTOSTART_FLAG_DBUS_ACTIVATABLE != 0 {
+        entry.push_str("DBusActivatable=true\n");
+    }
+
+    entry
+}
+
+pub fn enable(app_id: &str, commandline: &[String], flags: u32) -> io::Result<PathBuf> {
+    let path = path(app_id)?;
+    let entry = desktop_entry(app_id, commandline, flags);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    fs::write(&path, entry)?;
+
+    Ok(path)
+}
+
+pub fn disable(app_id: &str) -> io::Result<PathBuf> {
+    let path = path(app_id)?;
+
+    match fs::remove_file(&path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{desktop_entry, escape, quote};
+
+    fn exec(args: &[&str]) -> String {
+        let args: Vec<String> = args.iter().map(|arg| quote(arg)).collect();
+        escape(&args.join(" "))
+    }
+
+    #[test]
+    fn plain_arguments_are_not_quoted() {
+        assert_eq!(
+            exec(&["app", "--flag=1", "/tmp/a.txt"]),
+            "app --flag=1 /tmp/a.txt"
+        );
+    }
+
+    #[test]
+    fn reserved_characters_are_quoted_and_escaped() {
+        assert_eq!(exec(&["my file"]), r#""my file""#);
+        assert_eq!(exec(&["100%"]), "100%%");
+        assert_eq!(exec(&[""]), r#""""#);
+        assert_eq!(exec(&["$HOME"]), r#""\\$HOME""#);
+        assert_eq!(exec(&[r#"say "hi""#]), r#""say \\"hi\\"""#);
+        assert_eq!(exec(&[r"C:\dir"]), r#""C:\\\\dir""#);
+    }
+
+    #[test]
+    fn only_flatpak_apps_get_x_flatpak() {
+        let flatpak = ["flatpak", "run", "--command=app", "org.example.App"].map(String::from);
+        let host = ["/usr/bin/app", "--background"].map(String::from);
+
+        assert_eq!(
+            desktop_entry("org.example.App", &flatpak, 1),
+            "[Desktop Entry]\nType=Application\nName=org.example.App\n\
+             Exec=flatpak run --command=app org.example.App\n\
+             X-Flatpak=org.example.App\nDBusActivatable=true\n"
+        );
+        assert_eq!(
+            desktop_entry("app", &host, 0),
+            "[Desktop Entry]\nType=Application\nName=app\nExec=/usr/bin/app --background\n"
+        );
+    }
+}
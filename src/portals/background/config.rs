@@ -0,0 +1,46 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::{
+    portals::{appchooser::config::RunnerType, dialog::validate_runner},
+    utils::command::validate_command,
+};
+
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum AskMethod {
+    #[default]
+    Runner,
+    Notification,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "kebab-case")]
+#[schemars(deny_unknown_fields)]
+pub struct BackgroundConfig {
+    pub enabled: bool,
+    // how to ask when an app keeps running without a window, default: runner
+    pub ask: Option<AskMethod>,
+    // prints the app ids of all open windows, one per line
+    pub windows: Option<Vec<String>>,
+    pub runner: Option<RunnerType>,
+}
+
+impl BackgroundConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        problems.extend(validate_runner("background.runner", self.runner.as_ref()));
+
+        problems.extend(validate_command("background.windows", &self.windows));
+
+        if self.enabled && self.windows.is_none() {
+            problems.push(
+                "background.windows: is unset, apps running in the background can't be detected"
+                    .to_string(),
+            );
+        }
+
+        problems
+    }
+}
@@ -0,0 +1,4 @@
+mod autostart;
+pub mod config;
+mod policy;
+pub mod service;
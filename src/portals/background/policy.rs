@@ -0,0 +1,57 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{constants::CONFIG_APP_NAME, utils::xdg};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Policy {
+    Allow,
+    Forbid,
+}
+
+// what the user answered for each app, kept in $XDG_STATE_HOME so it can be edited by hand
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct Policies {
+    #[serde(default)]
+    pub apps: BTreeMap<String, Policy>,
+}
+
+impl Policies {
+    fn path() -> PathBuf {
+        xdg::state_home()
+            .join(CONFIG_APP_NAME)
+            .join("background.toml")
+    }
+
+    pub fn load() -> Policies {
+        let path = Policies::path();
+
+        let Ok(data) = fs::read_to_string(&path) else {
+            return Policies::default();
+        };
+
+        toml::from_str(&data).unwrap_or_else(|err| {
+            tracing::error!("Could not parse {:?}, starting over: {}", path, err);
+            Policies::default()
+        })
+    }
+
+    pub fn save(&self) {
+        let path = Policies::path();
+
+        let result = toml::to_string_pretty(self)
+            .map_err(std::io::Error::other)
+            .and_then(|data| {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                fs::write(&path, data)
+            });
+
+        if let Err(err) = result {
+            tracing::error!("Could not save {:?}: {}", path, err);
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+
+use futures_util::StreamExt;
+use zbus::{Connection, fdo, interface};
+use zvariant::{ObjectPath, OwnedValue, Value};
+
+use crate::{
+    portals::{dialog::Runner, notification::notifications::NotificationsProxy},
+    utils::command::run_template,
+};
+
+use super::{
+    autostart,
+    config::{AskMethod, BackgroundConfig},
+    policy::{Policies, Policy},
+};
+
+const APP_STATE_RUNNING: u32 = 1;
+
+const RESULT_FORBID: u32 = 0;
+const RESULT_ALLOW: u32 = 1;
+const RESULT_ALLOW_ONCE: u32 = 2;
+
+pub struct BackgroundService {
+    config: BackgroundConfig,
+    runner: Runner,
+    policies: Mutex<Policies>,
+}
+
+impl BackgroundService {
+    pub fn new(runner: Runner, config: BackgroundConfig) -> Self {
+        BackgroundService {
+            config,
+            runner,
+            policies: Mutex::new(Policies::load()),
+        }
+    }
+}
+
+#[interface(name = "org.freedesktop.impl.portal.Background")]
+impl BackgroundService {
+    #[zbus(property, name = "version")]
+    async fn version(&self) -> u32 {
+        1
+    }
+
+    // there is no portable way to tell which apps have windows, so the compositor is asked via
+    // the windows command. Apps missing here count as running in the background and the
+    // frontend asks via NotifyBackground
+    async fn get_app_state(&self) -> fdo::Result<HashMap<String, OwnedValue>> {
+        tracing::debug!("GetAppState called");
+
+        let Some(command) = &self.config.windows else {
+            return Err(fdo::Error::Failed(
+                "zenzai: background.windows is unset".to_string(),
+            ));
+        };
+
+        let app_ids = window_app_ids(command)
+            .await
+            .map_err(|err| fdo::Error::Failed(format!("zenzai: {}", err)))?;
+
+        Ok(app_ids
+            .into_iter()
+            .map(|app_id| (app_id, OwnedValue::from(APP_STATE_RUNNING)))
+            .collect())
+    }
+
+    async fn notify_background(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        handle: ObjectPath<'_>,
+        app_id: &str,
+        name: &str,
+    ) -> fdo::Result<(u32, HashMap<String, OwnedValue>)> {
+        tracing::debug!(
+            "NotifyBackground called with handle: {:?}, app_id: {:?}, name: {:?}",
+            handle,
+            app_id,
+            name
+        );
+
+        let remembered = self.policies.lock().unwrap().apps.get(app_id).copied();
+
+        let result = match remembered {
+            Some(Policy::Allow) => RESULT_ALLOW,
+            Some(Policy::Forbid) => RESULT_FORBID,
+            None => {
+                let name = if name.is_empty() { app_id } else { name };
+
+                let answer = match self.config.ask.unwrap_or_default() {
+                    AskMethod::Runner => self.ask_runner(name).await?,
+                    AskMethod::Notification => ask_notification(conn, app_id, name).await?,
+                };
+
+                let Some(result) = answer else {
+                    tracing::info!("NotifyBackground for {:?} was cancelled", app_id);
+                    return Ok((1, HashMap::new()));
+                };
+
+                let policy = match result {
+                    RESULT_ALLOW => Some(Policy::Allow),
+                    RESULT_FORBID => Some(Policy::Forbid),
+                    _ => None,
+                };
+
+                if let Some(policy) = policy {
+                    let mut policies = self.policies.lock().unwrap();
+                    policies.apps.insert(app_id.to_string(), policy);
+                    policies.save();
+                }
+
+                result
+            }
+        };
+
+        tracing::info!("Running {:?} in the background: {}", app_id, result);
+
+        let mut m = HashMap::new();
+        m.insert("result".to_string(), OwnedValue::from(result));
+
+        Ok((0, m))
+    }
+
+    async fn enable_autostart(
+        &self,
+        app_id: &str,
+        enable: bool,
+        commandline: Vec<String>,
+        flags: u32,
+    ) -> fdo::Result<bool> {
+        tracing::debug!(
+            "EnableAutostart called with app_id: {:?}, enable: {}, commandline: {:?}, flags: {}",
+            app_id,
+            enable,
+            commandline,
+            flags
+        );
+
+        let result = if enable {
+            autostart::enable(app_id, &commandline, flags)
+        } else {
+            autostart::disable(app_id)
+        };
+
+        match result {
+            Ok(path) => {
+                tracing::info!("Autostart for {:?} set to {}: {:?}", app_id, enable, path);
+                Ok(enable)
+            }
+            Err(err) => {
+                tracing::error!("Could not update autostart for {:?}: {}", app_id, err);
+                Ok(false)
+            }
+        }
+    }
+}
+
+impl BackgroundService {
+    async fn ask_runner(&self, name: &str) -> fdo::Result<Option<u32>> {
+        let answers = [
+            (RESULT_ALLOW, "always"),
+            (RESULT_ALLOW_ONCE, "this time"),
+            (RESULT_FORBID, "never"),
+        ];
+
+        let lines: Vec<String> = answers
+            .iter()
+            .map(|(_, answer)| format!("Allow {} to run in the background? {}", name, answer))
+            .collect();
+
+        let selection = self.runner.pick(&lines).await?;
+
+        Ok(selection.map(|i| answers[i].0))
+    }
+}
+
+async fn window_app_ids(command: &[String]) -> std::io::Result<HashSet<String>> {
+    let output = run_template(command, &[]).await?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "{} exited with {}",
+            command[0], output.status
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect())
+}
+
+// sends a notification with allow/forbid buttons and waits until one is clicked or it goes away
+async fn ask_notification(conn: &Connection, app_id: &str, name: &str) -> fdo::Result<Option<u32>> {
+    let proxy = NotificationsProxy::new(conn).await?;
+
+    let mut invoked = proxy.receive_action_invoked().await?;
+    let mut closed = proxy.receive_notification_closed().await?;
+
+    let hints = HashMap::from([("urgency", Value::U8(1))]);
+
+    let id = proxy
+        .notify(
+            "xdg-desktop-portal-zenzai",
+            0,
+            app_id,
+            &format!("{} is running in the background", name),
+            "Allow it to keep running without a window?",
+            &["allow", "Always", "once", "This time", "forbid", "Never"],
+            hints,
+            0,
+        )
+        .await?;
+
+    loop {
+        tokio::select! {
+            Some(signal) = invoked.next() => {
+                let args = signal.args()?;
+
+                if args.id != id {
+                    continue;
+                }
+
+                let _ = proxy.close_notification(id).await;
+
+                return Ok(match args.action_key {
+                    "allow" => Some(RESULT_ALLOW),
+                    "once" => Some(RESULT_ALLOW_ONCE),
+                    "forbid" => Some(RESULT_FORBID),
+                    _ => None,
+                });
+            }
+            Some(signal) = closed.next() => {
+                if signal.args()?.id == id {
+                    return Ok(None);
+                }
+            }
+            else => return Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zvariant::OwnedValue;
+
+    use super::{APP_STATE_RUNNING, BackgroundService};
+    use crate::portals::{background::config::BackgroundConfig, dialog::Runner};
+
+    #[tokio::test]
+    async fn apps_with_windows_are_running() {
+        let config = BackgroundConfig {
+            windows: Some(
+                [
+                    "sh",
+                    "-c",
+                    "printf 'org.example.App\\n\\nfoot\\norg.example.App\\n'",
+                ]
+                .map(String::from)
+                .to_vec(),
+            ),
+            ..Default::default()
+        };
+
+        let service = BackgroundService::new(Runner::default(), config);
+        let mut state: Vec<(String, OwnedValue)> =
+            service.get_app_state().await.unwrap().into_iter().collect();
+        state.sort_by(|a, b| a.0.cmp(&b.0));
+
+        assert_eq!(
+            state,
+            [
+                ("foot".to_string(), OwnedValue::from(APP_STATE_RUNNING)),
+                (
+                    "org.example.App".to_string(),
+                    OwnedValue::from(APP_STATE_RUNNING)
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn app_state_is_unknown_without_a_windows_command() {
+        let service = BackgroundService::new(Runner::default(), BackgroundConfig::default());
+        assert!(service.get_app_state().await.is_err());
+    }
+}
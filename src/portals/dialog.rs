@@ -0,0 +1,105 @@
+use which::which;
+use zbus::fdo;
+
+use super::appchooser::{
+    config::RunnerType,
+    run_command::{RunCommandError, run_picker_command},
+};
+
+// how the portals ask the user something, their own `runner` falls back to the appchooser one
+#[derive(Debug, Clone, Default)]
+pub struct Runner(Option<RunnerType>);
+
+impl Runner {
+    pub fn new(runner: Option<RunnerType>, fallback: Option<RunnerType>) -> Runner {
+        Runner(runner.or(fallback))
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.0.is_some()
+    }
+
+    // offers the lines and returns the index of the selected one, None if nothing was selected
+    pub async fn pick(&self, lines: &[String]) -> fdo::Result<Option<usize>> {
+        let Some(RunnerType::Dmenu(runner_cmd)) = &self.0 else {
+            return Err(fdo::Error::Failed(
+                "zenzai: runner type is unset, configure appchooser.runner".to_string(),
+            ));
+        };
+
+        match run_picker_command(runner_cmd, lines).await {
+            Ok(selection) => Ok(lines.iter().position(|line| line == selection.trim())),
+            Err(RunCommandError::NothingSelected) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    // a yes/no question, cancelling counts as no
+    pub async fn confirm(&self, question: &str) -> fdo::Result<bool> {
+        let answers = [format!("{}? yes", question), format!("{}? no", question)];
+
+        Ok(self.pick(&answers).await? == Some(0))
+    }
+}
+
+// the problems of a portal's own runner, `key` is e.g. "account.runner"
+pub fn validate_runner(key: &str, runner: Option<&RunnerType>) -> Vec<String> {
+    match runner {
+        Some(RunnerType::Dmenu(cmd)) if which(&cmd.command).is_err() => vec![format!(
+            "{}: command {:?} could not be found",
+            key, cmd.command
+        )],
+        _ => Vec::new(),
+    }
+}
+
+// app ids that don't need to be asked about, they can end with a wildcard, e.g. "org.mozilla.*"
+pub fn is_auto_approved(patterns: Option<&Vec<String>>, app_id: &str) -> bool {
+    patterns
+        .into_iter()
+        .flatten()
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => app_id.starts_with(prefix),
+            None => app_id == pattern,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Runner, is_auto_approved};
+    use crate::portals::appchooser::config::{Command, RunnerType};
+
+    // a runner that always selects the lines matching `pattern`
+    fn runner(pattern: &str) -> Runner {
+        Runner(Some(RunnerType::Dmenu(Command {
+            command: "grep".to_string(),
+            arguments: Some(vec!["-x".to_string(), pattern.to_string()]),
+            ..Default::default()
+        })))
+    }
+
+    #[test]
+    fn auto_approve_patterns_can_end_with_a_wildcard() {
+        let patterns = Some(vec![
+            "org.mozilla.*".to_string(),
+            "org.gnome.Maps".to_string(),
+        ]);
+
+        assert!(is_auto_approved(patterns.as_ref(), "org.mozilla.firefox"));
+        assert!(is_auto_approved(patterns.as_ref(), "org.gnome.Maps"));
+        assert!(!is_auto_approved(patterns.as_ref(), "org.gnome.Maps2"));
+        assert!(!is_auto_approved(None, "org.mozilla.firefox"));
+    }
+
+    #[tokio::test]
+    async fn cancelling_counts_as_no() {
+        assert!(runner("Continue? yes").confirm("Continue").await.unwrap());
+        assert!(!runner("Continue? no").confirm("Continue").await.unwrap());
+        assert!(!runner("nothing").confirm("Continue").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn an_unset_runner_is_an_error() {
+        assert!(Runner::default().confirm("Continue").await.is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use zbus::{fdo, interface};
+use zvariant::{ObjectPath, OwnedValue, Value};
+
+use crate::portals::dialog::Runner;
+
+use super::config::DynamicLauncherConfig;
+
+const LAUNCHER_TYPE_APPLICATION: u32 = 1;
+const LAUNCHER_TYPE_WEBAPP: u32 = 2;
+
+// the frontend writes the desktop file and icon itself once the install is approved, they end up
+// in $XDG_DATA_HOME/applications where the appchooser finds them
+pub struct DynamicLauncherService {
+    pub config: DynamicLauncherConfig,
+    pub runner: Runner,
+}
+
+#[interface(name = "org.freedesktop.impl.portal.DynamicLauncher")]
+impl DynamicLauncherService {
+    #[zbus(property, name = "version")]
+    async fn version(&self) -> u32 {
+        1
+    }
+
+    #[zbus(property)]
+    async fn supported_launcher_types(&self) -> u32 {
+        LAUNCHER_TYPE_APPLICATION | LAUNCHER_TYPE_WEBAPP
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn prepare_install(
+        &self,
+        handle: ObjectPath<'_>,
+        app_id: &str,
+        parent_window: &str,
+        name: &str,
+        icon: Value<'_>,
+        options: HashMap<&str, Value<'_>>,
+    ) -> fdo::Result<(u32, HashMap<String, OwnedValue>)> {
+        tracing::debug!(
+            "PrepareInstall called with handle: {:?}, app_id: {:?}, parent_window: {}, name: {:?}, options: {:?}",
+            handle,
+            app_id,
+            parent_window,
+            name,
+            options
+        );
+
+        let launcher = match (options.get("launcher_type"), options.get("target")) {
+            (Some(Value::U32(LAUNCHER_TYPE_WEBAPP)), Some(Value::Str(target))) => {
+                format!("web app {} ({})", name, target)
+            }
+            (Some(Value::U32(LAUNCHER_TYPE_WEBAPP)), _) => format!("web app {}", name),
+            _ => name.to_string(),
+        };
+
+        if !self.config.is_auto_approved(app_id)
+            && !self
+                .runner
+                .confirm(&format!("Install {}", launcher))
+                .await?
+        {
+            tracing::info!("Installing {} for {:?} was denied", launcher, app_id);
+            return Ok((1, HashMap::new()));
+        }
+
+        tracing::info!("Installing {} for {:?}", launcher, app_id);
+
+        let icon =
+            OwnedValue::try_from(icon).map_err(|err| fdo::Error::InvalidArgs(err.to_string()))?;
+
+        let mut m = HashMap::new();
+        m.insert("name".to_string(), zvariant::Str::from(name).into());
+        m.insert("icon".to_string(), icon);
+
+        Ok((0, m))
+    }
+
+    async fn request_install_token(
+        &self,
+        app_id: &str,
+        options: HashMap<&str, Value<'_>>,
+    ) -> fdo::Result<u32> {
+        tracing::debug!(
+            "RequestInstallToken called with app_id: {:?}, options: {:?}",
+            app_id,
+            options
+        );
+
+        let app = if app_id.is_empty() {
+            "this application"
+        } else {
+            app_id
+        };
+
+        if !self.config.is_auto_approved(app_id)
+            && !self
+                .runner
+                .confirm(&format!("Allow {} to install launchers", app))
+                .await?
+        {
+            tracing::info!("Install token for {:?} was denied", app_id);
+            return Ok(1);
+        }
+
+        Ok(0)
+    }
+}
@@ -0,0 +1,30 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use which::which;
+
+use crate::portals::appchooser::config::Command;
+
+#[derive(Debug, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "kebab-case")]
+#[schemars(deny_unknown_fields)]
+pub struct EmailConfig {
+    pub enabled: bool,
+    pub command: Option<Command>,
+}
+
+impl EmailConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if let Some(cmd) = &self.command
+            && which(&cmd.command).is_err()
+        {
+            problems.push(format!(
+                "email.command: command {:?} could not be found",
+                cmd.command
+            ));
+        }
+
+        problems
+    }
+}
@@ -0,0 +1,88 @@
+#[derive(Debug, Default)]
+pub struct Mail {
+    pub addresses: Vec<String>,
+    pub cc: Vec<String>,
+    pub bcc: Vec<String>,
+    pub subject: Option<String>,
+    pub body: Option<String>,
+    pub attachments: Vec<String>,
+}
+
+// percent-encodes everything except the unreserved characters of RFC 3986 (and @ for addresses)
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'@' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+impl Mail {
+    // mailto:to@example.com?cc=...&bcc=...&subject=...&body=...&attach=...
+    pub fn to_uri(&self) -> String {
+        let to: Vec<String> = self.addresses.iter().map(|addr| encode(addr)).collect();
+
+        let mut params: Vec<String> = Vec::new();
+
+        params.extend(self.cc.iter().map(|addr| format!("cc={}", encode(addr))));
+        params.extend(self.bcc.iter().map(|addr| format!("bcc={}", encode(addr))));
+
+        if let Some(subject) = &self.subject {
+            params.push(format!("subject={}", encode(subject)));
+        }
+
+        if let Some(body) = &self.body {
+            params.push(format!("body={}", encode(body)));
+        }
+
+        params.extend(
+            self.attachments
+                .iter()
+                .map(|path| format!("attach={}", encode(path))),
+        );
+
+        if params.is_empty() {
+            format!("mailto:{}", to.join(","))
+        } else {
+            format!("mailto:{}?{}", to.join(","), params.join("&"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Mail;
+
+    #[test]
+    fn uris_are_percent_encoded() {
+        let mail = Mail {
+            addresses: vec!["a@example.com".to_string(), "b@example.com".to_string()],
+            cc: vec!["c@example.com".to_string()],
+            bcc: vec![],
+            subject: Some("Hello & bye?".to_string()),
+            body: Some("Line 1\nÄ".to_string()),
+            attachments: vec!["/home/user/My Report.pdf".to_string()],
+        };
+
+        assert_eq!(
+            mail.to_uri(),
+            "mailto:a@example.com,b@example.com?cc=c@example.com&subject=Hello%20%26%20bye%3F\
+             &body=Line%201%0A%C3%84&attach=%2Fhome%2Fuser%2FMy%20Report.pdf"
+        );
+    }
+
+    #[test]
+    fn uris_without_parameters_have_no_query() {
+        let mail = Mail {
+            addresses: vec!["a@example.com".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(mail.to_uri(), "mailto:a@example.com");
+        assert_eq!(Mail::default().to_uri(), "mailto:");
+    }
+}
@@ -0,0 +1,3 @@
+pub mod config;
+mod mailto;
+pub mod service;
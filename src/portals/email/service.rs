@@ -0,0 +1,136 @@
+use std::{collections::HashMap, time::Duration};
+
+use zbus::{Connection, fdo, interface};
+use zvariant::{ObjectPath, OwnedValue, Value};
+
+use crate::{
+    portals::appchooser::{
+        config::{DefaultMapping, InputFile, LaunchMode, RunnerType},
+        desktop_files::default_handler,
+        run_command::{Launch, RunCommandError, run_command},
+        service::resolve_mapping,
+    },
+    terminal::Terminal,
+    utils::{hashmap::wildcard_get, options::string_option},
+};
+
+use super::{config::EmailConfig, mailto::Mail};
+
+const MAILTO_CONTENT_TYPE: &str = "x-scheme-handler/mailto";
+
+pub struct EmailService {
+    pub config: EmailConfig,
+    pub terminal: Option<Terminal>,
+    pub runner: Option<RunnerType>,
+    pub defaults: HashMap<String, DefaultMapping>,
+    // launched the same way as the appchooser does it
+    pub launch_mode: LaunchMode,
+    pub grace_period: Duration,
+}
+
+#[interface(name = "org.freedesktop.impl.portal.Email")]
+impl EmailService {
+    #[zbus(property, name = "version")]
+    async fn version(&self) -> u32 {
+        4
+    }
+
+    async fn compose_email(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        handle: ObjectPath<'_>,
+        app_id: &str,
+        window: &str,
+        options: HashMap<&str, Value<'_>>,
+    ) -> fdo::Result<(u32, HashMap<String, OwnedValue>)> {
+        tracing::debug!(
+            "ComposeEmail called with handle: {:?}, app_id: {:?}, window: {}, options: {:?}",
+            handle,
+            app_id,
+            window,
+            options
+        );
+
+        let mut addresses = string_list(&options, "addresses");
+
+        if let Some(address) = string_option(&options, "address") {
+            addresses.insert(0, address);
+        }
+
+        let mail = Mail {
+            addresses,
+            cc: string_list(&options, "cc"),
+            bcc: string_list(&options, "bcc"),
+            subject: string_option(&options, "subject"),
+            body: string_option(&options, "body"),
+            attachments: string_list(&options, "attachments"),
+        };
+
+        let uri = mail.to_uri();
+        let activation_token = string_option(&options, "activation_token");
+
+        tracing::info!("Composing email: {}", uri);
+
+        let input = InputFile {
+            path: uri.clone(),
+            uri,
+            content_type: MAILTO_CONTENT_TYPE.to_string(),
+            app_id: app_id.to_string(),
+        };
+
+        let launch = self.launch(&input).await.map_err(|err| {
+            tracing::error!("Could not find a mail client: {:?}", err);
+            fdo::Error::from(err)
+        })?;
+
+        run_command(
+            conn,
+            &launch,
+            activation_token.as_deref(),
+            self.launch_mode,
+            self.grace_period,
+        )
+        .await?;
+
+        Ok((0, HashMap::new()))
+    }
+}
+
+impl EmailService {
+    // the configured command, then the appchooser defaults and finally mimeapps.list
+    async fn launch(&self, input: &InputFile) -> Result<Launch, RunCommandError> {
+        if let Some(cmd) = &self.config.command {
+            return cmd.launch(input, self.terminal.as_ref());
+        }
+
+        if let Some((_, mapping)) = wildcard_get(&self.defaults, MAILTO_CONTENT_TYPE.to_string()) {
+            return resolve_mapping(
+                &mapping,
+                input,
+                self.terminal.as_ref(),
+                self.runner.as_ref(),
+            )
+            .await;
+        }
+
+        default_handler(MAILTO_CONTENT_TYPE)
+            .ok_or(RunCommandError::Other(format!(
+                "No application found for {}",
+                MAILTO_CONTENT_TYPE
+            )))
+            .and_then(|entry| entry.launch(self.terminal.as_ref(), input))
+    }
+}
+
+fn string_list(options: &HashMap<&str, Value<'_>>, key: &str) -> Vec<String> {
+    match options.get(key) {
+        Some(Value::Array(values)) => values
+            .iter()
+            .filter_map(|value| match value {
+                Value::Str(value) => Some(value.to_string()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
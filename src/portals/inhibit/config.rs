@@ -0,0 +1,24 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::utils::command::validate_command;
+
+#[derive(Debug, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "kebab-case")]
+#[schemars(deny_unknown_fields)]
+pub struct InhibitConfig {
+    pub enabled: bool,
+    pub on_inhibit: Option<Vec<String>>,
+    pub on_release: Option<Vec<String>>,
+}
+
+impl InhibitConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        problems.extend(validate_command("inhibit.on-inhibit", &self.on_inhibit));
+        problems.extend(validate_command("inhibit.on-release", &self.on_release));
+
+        problems
+    }
+}
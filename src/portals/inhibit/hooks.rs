@@ -0,0 +1,66 @@
+use std::sync::Mutex;
+
+use crate::utils::command::run_template;
+
+use super::{config::InhibitConfig, logind::flag_names};
+
+#[derive(Debug, Clone)]
+pub struct Inhibitor {
+    pub app_id: String,
+    pub reason: String,
+    pub flags: u32,
+}
+
+// on-inhibit runs when the first inhibitor is taken, on-release after the last one is gone
+pub struct Hooks {
+    config: InhibitConfig,
+    active: Mutex<usize>,
+}
+
+impl Hooks {
+    pub fn new(config: InhibitConfig) -> Self {
+        Hooks {
+            config,
+            active: Mutex::new(0),
+        }
+    }
+
+    pub fn acquire(&self, inhibitor: &Inhibitor) {
+        let mut active = self.active.lock().unwrap();
+        *active += 1;
+
+        if *active == 1
+            && let Some(command) = &self.config.on_inhibit
+        {
+            run(command, inhibitor);
+        }
+    }
+
+    pub fn release(&self, inhibitor: &Inhibitor) {
+        let mut active = self.active.lock().unwrap();
+        *active = active.saturating_sub(1);
+
+        if *active == 0
+            && let Some(command) = &self.config.on_release
+        {
+            run(command, inhibitor);
+        }
+    }
+}
+
+fn run(command: &[String], inhibitor: &Inhibitor) {
+    let command = command.to_vec();
+    let placeholders = [
+        ("{app_id}", inhibitor.app_id.clone()),
+        ("{reason}", inhibitor.reason.clone()),
+        ("{flags}", flag_names(inhibitor.flags)),
+    ];
+
+    tokio::spawn(async move {
+        match run_template(&command, &placeholders).await {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => tracing::warn!("Hook {} exited with {}", command[0], output.status),
+            Err(err) => tracing::error!("Hook failed: {}", err),
+        }
+    });
+}
@@ -0,0 +1,51 @@
+use zbus::proxy;
+use zvariant::OwnedFd;
+
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+pub trait Manager {
+    fn inhibit(&self, what: &str, who: &str, why: &str, mode: &str) -> zbus::Result<OwnedFd>;
+
+    #[zbus(signal)]
+    fn prepare_for_shutdown(&self, start: bool) -> zbus::Result<()>;
+}
+
+pub const FLAG_LOGOUT: u32 = 1;
+pub const FLAG_USER_SWITCH: u32 = 2;
+pub const FLAG_SUSPEND: u32 = 4;
+pub const FLAG_IDLE: u32 = 8;
+
+// logind has no lock for logging out or switching users, those are only seen by the hooks
+pub fn inhibit_what(flags: u32) -> String {
+    let mut what = Vec::new();
+
+    if flags & FLAG_SUSPEND != 0 {
+        what.push("sleep");
+    }
+
+    if flags & FLAG_IDLE != 0 {
+        what.push("idle");
+    }
+
+    what.join(":")
+}
+
+// used for the {flags} placeholder of the hook commands
+pub fn flag_names(flags: u32) -> String {
+    let names = [
+        (FLAG_LOGOUT, "logout"),
+        (FLAG_USER_SWITCH, "user-switch"),
+        (FLAG_SUSPEND, "suspend"),
+        (FLAG_IDLE, "idle"),
+    ];
+
+    names
+        .iter()
+        .filter(|(flag, _)| flags & flag != 0)
+        .map(|(_, name)| *name)
+        .collect::<Vec<&str>>()
+        .join(",")
+}
@@ -0,0 +1,6 @@
+pub mod config;
+mod hooks;
+mod logind;
+mod request;
+pub mod service;
+mod session;
@@ -0,0 +1,33 @@
+use std::sync::Arc;
+
+use zbus::{ObjectServer, fdo, interface, message::Header};
+use zvariant::OwnedFd;
+
+use super::hooks::{Hooks, Inhibitor};
+
+// holds the logind lock until the frontend closes the request
+pub struct InhibitRequest {
+    pub fd: Option<OwnedFd>,
+    pub inhibitor: Inhibitor,
+    pub hooks: Arc<Hooks>,
+}
+
+#[interface(name = "org.freedesktop.impl.portal.Request")]
+impl InhibitRequest {
+    async fn close(
+        &mut self,
+        #[zbus(object_server)] server: &ObjectServer,
+        #[zbus(header)] header: Header<'_>,
+    ) -> fdo::Result<()> {
+        tracing::info!("Releasing inhibitor for {:?}", self.inhibitor);
+
+        self.fd.take();
+        self.hooks.release(&self.inhibitor);
+
+        if let Some(path) = header.path() {
+            server.remove::<InhibitRequest, _>(path.to_owned()).await?;
+        }
+
+        Ok(())
+    }
+}
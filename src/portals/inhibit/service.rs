@@ -0,0 +1,551 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use futures_util::StreamExt;
+use tokio::sync::OnceCell;
+use zbus::{Connection, ObjectServer, fdo, interface, object_server::SignalEmitter};
+use zvariant::{ObjectPath, OwnedObjectPath, Value};
+
+use super::{
+    config::InhibitConfig,
+    hooks::{Hooks, Inhibitor},
+    logind::{ManagerProxy, inhibit_what},
+    request::InhibitRequest,
+    session::MonitorSession,
+};
+
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+
+const SESSION_STATE_RUNNING: u32 = 1;
+const SESSION_STATE_QUERY_END: u32 = 2;
+
+pub struct InhibitService {
+    hooks: Arc<Hooks>,
+    system: OnceCell<Connection>,
+    monitors: Arc<Mutex<HashSet<OwnedObjectPath>>>,
+    watching: Arc<AtomicBool>,
+}
+
+impl InhibitService {
+    pub fn new(config: InhibitConfig) -> Self {
+        InhibitService {
+            hooks: Arc::new(Hooks::new(config)),
+            system: OnceCell::new(),
+            monitors: Arc::new(Mutex::new(HashSet::new())),
+            watching: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    async fn system(&self) -> zbus::Result<&Connection> {
+        self.system.get_or_try_init(Connection::system).await
+    }
+}
+
+#[interface(name = "org.freedesktop.impl.portal.Inhibit")]
+impl InhibitService {
+    #[zbus(property, name = "version")]
+    async fn version(&self) -> u32 {
+        3
+    }
+
+    async fn inhibit(
+        &self,
+        #[zbus(object_server)] server: &ObjectServer,
+        handle: OwnedObjectPath,
+        app_id: &str,
+        window: &str,
+        flags: u32,
+        options: HashMap<&str, Value<'_>>,
+    ) -> fdo::Result<()> {
+        tracing::debug!(
+            "Inhibit called with handle: {:?}, app_id: {:?}, window: {}, flags: {}, options: {:?}",
+            handle,
+            app_id,
+            window,
+            flags,
+            options
+        );
+
+        let reason = match options.get("reason") {
+            Some(Value::Str(reason)) => reason.to_string(),
+            _ => String::new(),
+        };
+
+        let inhibitor = Inhibitor {
+            app_id: app_id.to_string(),
+            reason,
+            flags,
+        };
+
+        let what = inhibit_what(flags);
+
+        // without logind the hooks can still do their job, so this is not fatal
+        let fd = if what.is_empty() {
+            None
+        } else {
+            match self.take_lock(&what, &inhibitor).await {
+                Ok(fd) => Some(fd),
+                Err(err) => {
+                    tracing::warn!("Could not take logind inhibitor lock {:?}: {}", what, err);
+                    None
+                }
+            }
+        };
+
+        // the hooks only count inhibitors that can be released via the request
+        let exported = server
+            .at(
+                handle.clone(),
+                InhibitRequest {
+                    fd,
+                    inhibitor: inhibitor.clone(),
+                    hooks: self.hooks.clone(),
+                },
+            )
+            .await?;
+
+        if !exported {
+            return Err(fdo::Error::Failed(format!(
+                "zenzai: request {} already exists",
+                handle
+            )));
+        }
+
+        tracing::info!("Inhibiting {:?} for {:?}", what, inhibitor);
+
+        self.hooks.acquire(&inhibitor);
+
+        Ok(())
+    }
+
+    async fn create_monitor(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        #[zbus(object_server)] server: &ObjectServer,
+        handle: ObjectPath<'_>,
+        session_handle: OwnedObjectPath,
+        app_id: &str,
+        window: &str,
+    ) -> fdo::Result<u32> {
+        tracing::debug!(
+            "CreateMonitor called with handle: {:?}, session_handle: {:?}, app_id: {:?}, window: {}",
+            handle,
+            session_handle,
+            app_id,
+            window
+        );
+
+        server
+            .at(
+                session_handle.clone(),
+                MonitorSession {
+                    monitors: self.monitors.clone(),
+                },
+            )
+            .await?;
+
+        self.monitors.lock().unwrap().insert(session_handle.clone());
+
+        // a new watcher is started with the next monitor if this one gives up
+        if !self.watching.swap(true, Ordering::SeqCst) {
+            match self.system().await {
+                Ok(system) => {
+                    let watch = watch_shutdown(conn.clone(), system.clone(), self.monitors.clone());
+                    let watching = self.watching.clone();
+
+                    tokio::spawn(async move {
+                        match watch.await {
+                            Ok(()) => tracing::warn!("Stopped watching for shutdowns"),
+                            Err(err) => tracing::error!("Stopped watching for shutdowns: {}", err),
+                        }
+                        watching.store(false, Ordering::SeqCst);
+                    });
+                }
+                Err(err) => {
+                    tracing::warn!("Could not connect to the system bus: {}", err);
+                    self.watching.store(false, Ordering::SeqCst);
+                }
+            }
+        }
+
+        // the frontend only knows about the session once we replied. The object server holds a
+        // read lock on this interface until the reply is sent, so taking the write lock waits for it
+        let conn = conn.clone();
+
+        tokio::spawn(async move {
+            let result = async {
+                let iface = conn
+                    .object_server()
+                    .interface::<_, InhibitService>(PORTAL_PATH)
+                    .await?;
+                drop(iface.get_mut().await);
+
+                emit_state_changed(&conn, &session_handle, SESSION_STATE_RUNNING).await
+            };
+
+            if let Err(err) = result.await {
+                tracing::error!("Could not emit StateChanged: {}", err);
+            }
+        });
+
+        Ok(0)
+    }
+
+    async fn query_end_response(&self, session_handle: ObjectPath<'_>) -> fdo::Result<()> {
+        tracing::info!("QueryEndResponse from {}", session_handle);
+        Ok(())
+    }
+
+    #[zbus(signal)]
+    async fn state_changed(
+        emitter: &SignalEmitter<'_>,
+        session_handle: ObjectPath<'_>,
+        state: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<()>;
+}
+
+impl InhibitService {
+    async fn take_lock(
+        &self,
+        what: &str,
+        inhibitor: &Inhibitor,
+    ) -> zbus::Result<zvariant::OwnedFd> {
+        let proxy = ManagerProxy::new(self.system().await?).await?;
+
+        let who = if inhibitor.app_id.is_empty() {
+            "xdg-desktop-portal-zenzai"
+        } else {
+            inhibitor.app_id.as_str()
+        };
+
+        proxy.inhibit(what, who, &inhibitor.reason, "block").await
+    }
+}
+
+async fn emit_state_changed(
+    conn: &Connection,
+    session_handle: &OwnedObjectPath,
+    session_state: u32,
+) -> zbus::Result<()> {
+    let emitter = SignalEmitter::new(conn, PORTAL_PATH)?;
+
+    let state = HashMap::from([
+        ("screensaver-active", Value::Bool(false)),
+        ("session-state", Value::U32(session_state)),
+    ]);
+
+    InhibitService::state_changed(&emitter, session_handle.as_ref(), state).await
+}
+
+// tell all monitors when logind is about to shut down so apps get a chance to save their state
+async fn watch_shutdown(
+    conn: Connection,
+    system: Connection,
+    monitors: Arc<Mutex<HashSet<OwnedObjectPath>>>,
+) -> zbus::Result<()> {
+    let proxy = ManagerProxy::new(&system).await?;
+    let mut stream = proxy.receive_prepare_for_shutdown().await?;
+
+    while let Some(signal) = stream.next().await {
+        let start = match signal.args() {
+            Ok(args) => args.start,
+            Err(err) => {
+                tracing::warn!("Ignoring invalid PrepareForShutdown signal: {}", err);
+                continue;
+            }
+        };
+
+        let session_state = if start {
+            SESSION_STATE_QUERY_END
+        } else {
+            SESSION_STATE_RUNNING
+        };
+
+        let monitors: Vec<OwnedObjectPath> = monitors.lock().unwrap().iter().cloned().collect();
+
+        for session_handle in monitors {
+            if let Err(err) = emit_state_changed(&conn, &session_handle, session_state).await {
+                tracing::error!(
+                    "Could not emit StateChanged for {}: {}",
+                    session_handle,
+                    err
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{HashMap, HashSet},
+        fs::File,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use futures_util::StreamExt;
+    use zbus::{MessageStream, interface, message::Type, object_server::SignalEmitter};
+    use zvariant::{ObjectPath, OwnedFd, OwnedObjectPath, OwnedValue};
+
+    use super::{
+        InhibitService, PORTAL_PATH, SESSION_STATE_QUERY_END, SESSION_STATE_RUNNING, watch_shutdown,
+    };
+    use crate::{
+        portals::inhibit::{
+            config::InhibitConfig,
+            hooks::Inhibitor,
+            logind::{FLAG_IDLE, FLAG_LOGOUT, FLAG_SUSPEND, inhibit_what},
+        },
+        utils::testing::p2p_pair,
+    };
+
+    const LOGIND_PATH: &str = "/org/freedesktop/login1";
+
+    #[derive(Default)]
+    struct MockLogind {
+        locks: Arc<Mutex<Vec<[String; 4]>>>,
+    }
+
+    #[interface(name = "org.freedesktop.login1.Manager")]
+    impl MockLogind {
+        fn inhibit(&self, what: &str, who: &str, why: &str, mode: &str) -> OwnedFd {
+            self.locks
+                .lock()
+                .unwrap()
+                .push([what, who, why, mode].map(|arg| arg.to_string()));
+
+            std::os::fd::OwnedFd::from(File::open("/dev/null").unwrap()).into()
+        }
+
+        #[zbus(signal)]
+        async fn prepare_for_shutdown(emitter: &SignalEmitter<'_>, start: bool)
+        -> zbus::Result<()>;
+    }
+
+    #[test]
+    fn logout_takes_no_logind_lock() {
+        assert_eq!(inhibit_what(FLAG_LOGOUT), "");
+        assert_eq!(
+            inhibit_what(FLAG_LOGOUT | FLAG_SUSPEND | FLAG_IDLE),
+            "sleep:idle"
+        );
+    }
+
+    #[tokio::test]
+    async fn locks_are_taken_from_logind() {
+        let (logind, system) = p2p_pair().await;
+        let logind_mock = MockLogind::default();
+        let locks = logind_mock.locks.clone();
+        logind
+            .object_server()
+            .at(LOGIND_PATH, logind_mock)
+            .await
+            .unwrap();
+
+        let service = InhibitService::new(InhibitConfig::default());
+        service.system.set(system).unwrap();
+
+        let inhibitor = Inhibitor {
+            app_id: "org.example.App".to_string(),
+            reason: "Playing a video".to_string(),
+            flags: FLAG_SUSPEND | FLAG_IDLE,
+        };
+
+        service
+            .take_lock(&inhibit_what(inhibitor.flags), &inhibitor)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *locks.lock().unwrap(),
+            [["sleep:idle", "org.example.App", "Playing a video", "block"].map(String::from)]
+        );
+    }
+
+    #[tokio::test]
+    async fn monitors_are_told_about_shutdowns() {
+        let (logind, system) = p2p_pair().await;
+        logind
+            .object_server()
+            .at(LOGIND_PATH, MockLogind::default())
+            .await
+            .unwrap();
+
+        let (portal, frontend) = p2p_pair().await;
+        let mut messages = MessageStream::from(&frontend);
+
+        let session: OwnedObjectPath = "/org/freedesktop/portal/desktop/session/1_1/t"
+            .try_into()
+            .unwrap();
+        let monitors = Arc::new(Mutex::new(HashSet::from([session.clone()])));
+
+        tokio::spawn(watch_shutdown(portal, system, monitors));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let emitter = SignalEmitter::new(&logind, LOGIND_PATH).unwrap();
+        MockLogind::prepare_for_shutdown(&emitter, true)
+            .await
+            .unwrap();
+
+        let message = tokio::time::timeout(Duration::from_secs(5), async {
+            while let Some(Ok(message)) = messages.next().await {
+                if message.message_type() == Type::Signal
+                    && message
+                        .header()
+                        .member()
+                        .is_some_and(|m| m == "StateChanged")
+                {
+                    return message;
+                }
+            }
+            panic!("connection closed");
+        })
+        .await
+        .expect("no StateChanged signal");
+
+        let (handle, state): (OwnedObjectPath, HashMap<String, OwnedValue>) =
+            message.body().deserialize().unwrap();
+
+        assert_eq!(handle, session);
+        assert_eq!(
+            u32::try_from(&state["session-state"]).unwrap(),
+            SESSION_STATE_QUERY_END
+        );
+    }
+
+    #[tokio::test]
+    async fn the_initial_state_is_sent_after_the_reply() {
+        let (logind, system) = p2p_pair().await;
+        logind
+            .object_server()
+            .at(LOGIND_PATH, MockLogind::default())
+            .await
+            .unwrap();
+
+        let service = InhibitService::new(InhibitConfig::default());
+        service.system.set(system).unwrap();
+
+        let (portal, frontend) = p2p_pair().await;
+        portal
+            .object_server()
+            .at(PORTAL_PATH, service)
+            .await
+            .unwrap();
+        let mut messages = MessageStream::from(&frontend);
+
+        let session =
+            ObjectPath::from_static_str("/org/freedesktop/portal/desktop/session/1_1/t").unwrap();
+        let request =
+            ObjectPath::from_static_str("/org/freedesktop/portal/desktop/request/1_1/t").unwrap();
+
+        frontend
+            .call_method(
+                None::<&str>,
+                PORTAL_PATH,
+                Some("org.freedesktop.impl.portal.Inhibit"),
+                "CreateMonitor",
+                &(request, &session, "org.example.App", ""),
+            )
+            .await
+            .unwrap();
+
+        let mut replied = false;
+
+        let message = tokio::time::timeout(Duration::from_secs(5), async {
+            while let Some(Ok(message)) = messages.next().await {
+                match message.message_type() {
+                    Type::MethodReturn => replied = true,
+                    Type::Signal => return message,
+                    _ => {}
+                }
+            }
+            panic!("connection closed");
+        })
+        .await
+        .expect("no StateChanged signal");
+
+        assert!(replied, "StateChanged was sent before the reply");
+
+        let (handle, state): (OwnedObjectPath, HashMap<String, OwnedValue>) =
+            message.body().deserialize().unwrap();
+
+        assert_eq!(handle.as_ref(), session);
+        assert_eq!(
+            u32::try_from(&state["session-state"]).unwrap(),
+            SESSION_STATE_RUNNING
+        );
+    }
+
+    #[tokio::test]
+    async fn a_reused_handle_does_not_keep_the_hooks_active() {
+        let released =
+            std::env::temp_dir().join(format!("zenzai-inhibit-test-{:08x}", rand::random::<u32>()));
+
+        let service = InhibitService::new(InhibitConfig {
+            enabled: true,
+            on_inhibit: None,
+            on_release: Some(vec![
+                "touch".to_string(),
+                released.to_string_lossy().to_string(),
+            ]),
+        });
+
+        let (portal, frontend) = p2p_pair().await;
+        portal
+            .object_server()
+            .at(PORTAL_PATH, service)
+            .await
+            .unwrap();
+
+        let handle =
+            ObjectPath::from_static_str("/org/freedesktop/portal/desktop/request/1_1/t").unwrap();
+        let args = (
+            &handle,
+            "org.example.App",
+            "",
+            FLAG_LOGOUT,
+            HashMap::<&str, zvariant::Value>::new(),
+        );
+        let inhibit = || {
+            frontend.call_method(
+                None::<&str>,
+                PORTAL_PATH,
+                Some("org.freedesktop.impl.portal.Inhibit"),
+                "Inhibit",
+                &args,
+            )
+        };
+
+        inhibit().await.unwrap();
+        assert!(inhibit().await.is_err());
+
+        frontend
+            .call_method(
+                None::<&str>,
+                &handle,
+                Some("org.freedesktop.impl.portal.Request"),
+                "Close",
+                &(),
+            )
+            .await
+            .unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !released.exists() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("on-release did not run");
+
+        std::fs::remove_file(released).unwrap();
+    }
+}
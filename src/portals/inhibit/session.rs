@@ -0,0 +1,41 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use zbus::{ObjectServer, fdo, interface, message::Header};
+use zvariant::OwnedObjectPath;
+
+// a monitor created by CreateMonitor, it receives StateChanged signals until closed
+pub struct MonitorSession {
+    pub monitors: Arc<Mutex<HashSet<OwnedObjectPath>>>,
+}
+
+#[interface(name = "org.freedesktop.impl.portal.Session")]
+impl MonitorSession {
+    #[zbus(property, name = "version")]
+    async fn version(&self) -> u32 {
+        1
+    }
+
+    async fn close(
+        &self,
+        #[zbus(object_server)] server: &ObjectServer,
+        #[zbus(header)] header: Header<'_>,
+    ) -> fdo::Result<()> {
+        let Some(path) = header.path() else {
+            return Ok(());
+        };
+
+        tracing::info!("Closing monitor {}", path);
+
+        self.monitors
+            .lock()
+            .unwrap()
+            .remove(&OwnedObjectPath::from(path.to_owned()));
+
+        server.remove::<MonitorSession, _>(path.to_owned()).await?;
+
+        Ok(())
+    }
+}
@@ -1,3 +1,14 @@
+pub mod access;
+pub mod account;
 pub mod appchooser;
+pub mod background;
+pub mod dialog;
+pub mod dynamic_launcher;
+pub mod email;
+pub mod inhibit;
+pub mod notification;
+pub mod print;
+pub mod screenshot;
 pub mod secret;
 pub mod settings;
+pub mod wallpaper;
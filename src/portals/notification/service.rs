@@ -0,0 +1,554 @@
+use std::{
+    collections::HashMap,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use futures_util::StreamExt;
+use zbus::{Connection, fdo, interface, object_server::SignalEmitter};
+use zvariant::{OwnedValue, Value};
+
+use crate::utils::{options::string_option, uri::file_uri_to_path, xdg};
+
+use super::notifications::NotificationsProxy;
+
+// a notification sent to the notification server and the portal actions behind its action keys
+struct Entry {
+    app_id: String,
+    id: String,
+    actions: HashMap<String, (String, Option<OwnedValue>)>,
+}
+
+#[derive(Default)]
+struct Notifications {
+    ids: HashMap<(String, String), u32>,
+    entries: HashMap<u32, Entry>,
+}
+
+impl Notifications {
+    fn remove(&mut self, server_id: u32) -> Option<Entry> {
+        let entry = self.entries.remove(&server_id)?;
+        self.ids.remove(&(entry.app_id.clone(), entry.id.clone()));
+        Some(entry)
+    }
+}
+
+#[derive(Default)]
+pub struct NotificationService {
+    notifications: Arc<Mutex<Notifications>>,
+    watching: Arc<AtomicBool>,
+}
+
+#[interface(name = "org.freedesktop.impl.portal.Notification")]
+impl NotificationService {
+    #[zbus(property, name = "version")]
+    async fn version(&self) -> u32 {
+        1
+    }
+
+    async fn add_notification(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        app_id: &str,
+        id: &str,
+        notification: HashMap<&str, Value<'_>>,
+    ) -> fdo::Result<()> {
+        tracing::debug!(
+            "AddNotification called with app_id: {:?}, id: {:?}, notification: {:?}",
+            app_id,
+            id,
+            notification
+        );
+
+        let proxy = NotificationsProxy::new(conn).await?;
+
+        // a new watcher is started with the next notification if this one gives up
+        if !self.watching.swap(true, Ordering::SeqCst) {
+            let watch = watch_signals(conn.clone(), proxy.clone(), self.notifications.clone());
+            let watching = self.watching.clone();
+
+            tokio::spawn(async move {
+                if let Err(err) = watch.await {
+                    tracing::error!("Stopped watching notification signals: {}", err);
+                }
+                watching.store(false, Ordering::SeqCst);
+            });
+        }
+
+        let title = string_option(&notification, "title").unwrap_or_default();
+        let body = string_option(&notification, "body").unwrap_or_default();
+
+        let mut hints = HashMap::new();
+
+        if !app_id.is_empty() {
+            hints.insert("desktop-entry", Value::from(app_id));
+        }
+
+        let urgency: u8 = match string_option(&notification, "priority").as_deref() {
+            Some("low") => 0,
+            Some("urgent") => 2,
+            _ => 1,
+        };
+        hints.insert("urgency", Value::U8(urgency));
+
+        let mut icon = String::new();
+
+        match notification.get("icon").map(Icon::from_value) {
+            Some(Some(Icon::Name(name))) => icon = name,
+            Some(Some(Icon::Path(path))) => {
+                hints.insert("image-path", Value::from(path));
+            }
+            Some(None) => tracing::warn!("Unsupported icon: {:?}", notification.get("icon")),
+            None => {}
+        }
+
+        // action keys sent to the notification server -> (portal action, target)
+        let mut actions = HashMap::new();
+        let mut server_actions: Vec<String> = Vec::new();
+
+        if let Some(action) = string_option(&notification, "default-action") {
+            let target = target_option(notification.get("default-action-target"))?;
+            actions.insert("default".to_string(), (action, target));
+            server_actions.extend(["default".to_string(), String::new()]);
+        }
+
+        if let Some(Value::Array(buttons)) = notification.get("buttons") {
+            for (i, button) in buttons.iter().enumerate() {
+                let Value::Dict(button) = button else {
+                    continue;
+                };
+
+                let button: HashMap<String, OwnedValue> = button
+                    .try_clone()
+                    .and_then(|button| button.try_into())
+                    .map_err(|err| fdo::Error::InvalidArgs(err.to_string()))?;
+
+                let (Some(Value::Str(label)), Some(Value::Str(action))) = (
+                    button.get("label").map(|value| &**value),
+                    button.get("action").map(|value| &**value),
+                ) else {
+                    tracing::warn!("Ignoring button without label or action: {:?}", button);
+                    continue;
+                };
+
+                let key = format!("button-{}", i);
+                let target = target_option(button.get("target").map(|value| &**value))?;
+
+                actions.insert(key.clone(), (action.to_string(), target));
+                server_actions.extend([key, label.to_string()]);
+            }
+        }
+
+        let server_actions: Vec<&str> = server_actions.iter().map(|key| key.as_str()).collect();
+
+        let key = (app_id.to_string(), id.to_string());
+        let replaces_id = self
+            .notifications
+            .lock()
+            .unwrap()
+            .ids
+            .get(&key)
+            .copied()
+            .unwrap_or_default();
+
+        let app_name = if app_id.is_empty() {
+            "xdg-desktop-portal-zenzai"
+        } else {
+            app_id
+        };
+
+        let server_id = proxy
+            .notify(
+                app_name,
+                replaces_id,
+                &icon,
+                &title,
+                &body,
+                &server_actions,
+                hints,
+                -1,
+            )
+            .await?;
+
+        tracing::info!("Notification {:?} sent as {}", key, server_id);
+
+        let mut notifications = self.notifications.lock().unwrap();
+
+        if replaces_id != 0 && replaces_id != server_id {
+            notifications.remove(replaces_id);
+        }
+
+        notifications.ids.insert(key, server_id);
+        notifications.entries.insert(
+            server_id,
+            Entry {
+                app_id: app_id.to_string(),
+                id: id.to_string(),
+                actions,
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn remove_notification(
+        &self,
+        #[zbus(connection)] conn: &Connection,
+        app_id: &str,
+        id: &str,
+    ) -> fdo::Result<()> {
+        tracing::debug!(
+            "RemoveNotification called with app_id: {:?}, id: {:?}",
+            app_id,
+            id
+        );
+
+        let server_id = {
+            let mut notifications = self.notifications.lock().unwrap();
+
+            let Some(server_id) = notifications
+                .ids
+                .get(&(app_id.to_string(), id.to_string()))
+                .copied()
+            else {
+                return Ok(());
+            };
+
+            notifications.remove(server_id);
+            server_id
+        };
+
+        NotificationsProxy::new(conn)
+            .await?
+            .close_notification(server_id)
+            .await?;
+
+        Ok(())
+    }
+
+    #[zbus(signal)]
+    async fn action_invoked(
+        emitter: &SignalEmitter<'_>,
+        app_id: &str,
+        id: &str,
+        action: &str,
+        parameter: Vec<OwnedValue>,
+    ) -> zbus::Result<()>;
+}
+
+enum Icon {
+    Name(String),
+    Path(String),
+}
+
+impl Icon {
+    // icons are serialized GIcons: ("themed", ["name", ...]), ("file", "uri") or ("bytes", [...])
+    fn from_value(value: &Value<'_>) -> Option<Icon> {
+        let Value::Structure(icon) = value else {
+            return match value {
+                Value::Str(name) => Some(Icon::Name(name.to_string())),
+                _ => None,
+            };
+        };
+
+        let [Value::Str(kind), data] = icon.fields() else {
+            return None;
+        };
+
+        let data = match data {
+            Value::Value(data) => data,
+            data => data,
+        };
+
+        match (kind.as_str(), data) {
+            ("themed", Value::Array(names)) => match names.first() {
+                Some(Value::Str(name)) => Some(Icon::Name(name.to_string())),
+                _ => None,
+            },
+            ("file", Value::Str(uri)) => {
+                file_uri_to_path(uri).map(|path| Icon::Path(path.to_string_lossy().to_string()))
+            }
+            ("bytes", Value::Array(bytes)) => {
+                let bytes: Vec<u8> = bytes
+                    .iter()
+                    .filter_map(|byte| match byte {
+                        Value::U8(byte) => Some(*byte),
+                        _ => None,
+                    })
+                    .collect();
+
+                write_icon(&bytes).map(Icon::Path)
+            }
+            _ => None,
+        }
+    }
+}
+
+// notification servers want a path, the same image always ends up in the same file
+fn write_icon(bytes: &[u8]) -> Option<String> {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+
+    let name = format!("{:016x}", hasher.finish());
+
+    let path = xdg::private_runtime_dir("notification-icons").and_then(|dir| {
+        let path = dir.join(name);
+        if !path.exists() {
+            fs::write(&path, bytes)?;
+        }
+        Ok(path)
+    });
+
+    match path {
+        Ok(path) => Some(path.to_string_lossy().to_string()),
+        Err(err) => {
+            tracing::error!("Could not write notification icon: {}", err);
+            None
+        }
+    }
+}
+
+fn target_option(target: Option<&Value<'_>>) -> fdo::Result<Option<OwnedValue>> {
+    target
+        .map(|target| OwnedValue::try_from(target.try_clone()?))
+        .transpose()
+        .map_err(|err: zvariant::Error| fdo::Error::InvalidArgs(err.to_string()))
+}
+
+// translates the notification server signals back to the app that sent the notification
+async fn watch_signals(
+    conn: Connection,
+    proxy: NotificationsProxy<'static>,
+    notifications: Arc<Mutex<Notifications>>,
+) -> zbus::Result<()> {
+    let emitter = SignalEmitter::new(&conn, "/org/freedesktop/portal/desktop")?;
+
+    let mut invoked = proxy.receive_action_invoked().await?;
+    let mut closed = proxy.receive_notification_closed().await?;
+
+    loop {
+        tokio::select! {
+            Some(signal) = invoked.next() => {
+                let args = match signal.args() {
+                    Ok(args) => args,
+                    Err(err) => {
+                        tracing::warn!("Ignoring invalid ActionInvoked signal: {}", err);
+                        continue;
+                    }
+                };
+
+                let action = notifications
+                    .lock()
+                    .unwrap()
+                    .entries
+                    .get(&args.id)
+                    .and_then(|entry| {
+                        let (action, target) = entry.actions.get(args.action_key)?;
+                        let target = target.as_ref().and_then(|target| target.try_clone().ok());
+                        Some((entry.app_id.clone(), entry.id.clone(), action.clone(), target))
+                    });
+
+                let Some((app_id, id, action, target)) = action else {
+                    continue;
+                };
+
+                tracing::info!("Action {:?} invoked on {:?} of {:?}", action, id, app_id);
+
+                if let Err(err) = NotificationService::action_invoked(
+                    &emitter,
+                    &app_id,
+                    &id,
+                    &action,
+                    target.into_iter().collect(),
+                )
+                .await
+                {
+                    tracing::error!("Could not emit ActionInvoked for {:?}: {}", id, err);
+                }
+            }
+            Some(signal) = closed.next() => {
+                match signal.args() {
+                    Ok(args) => {
+                        notifications.lock().unwrap().remove(args.id);
+                    }
+                    Err(err) => tracing::warn!("Ignoring invalid NotificationClosed signal: {}", err),
+                }
+            }
+            else => break,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use futures_util::StreamExt;
+    use zbus::{Connection, MessageStream, interface, message::Type, object_server::SignalEmitter};
+    use zvariant::{OwnedValue, Value};
+
+    use super::NotificationService;
+    use crate::utils::testing::p2p_pair;
+
+    const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+
+    // (summary, body, actions, urgency) of every notification
+    type Sent = Arc<Mutex<Vec<(String, String, Vec<String>, u8)>>>;
+
+    #[derive(Default)]
+    struct MockNotifications {
+        sent: Sent,
+    }
+
+    #[interface(name = "org.freedesktop.Notifications")]
+    impl MockNotifications {
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &self,
+            _app_name: &str,
+            _replaces_id: u32,
+            _app_icon: &str,
+            summary: &str,
+            body: &str,
+            actions: Vec<String>,
+            hints: HashMap<String, OwnedValue>,
+            _expire_timeout: i32,
+        ) -> u32 {
+            let urgency = hints
+                .get("urgency")
+                .and_then(|urgency| u8::try_from(urgency).ok())
+                .unwrap_or_default();
+
+            let mut sent = self.sent.lock().unwrap();
+            sent.push((summary.to_string(), body.to_string(), actions, urgency));
+            sent.len() as u32
+        }
+
+        fn close_notification(&self, _id: u32) {}
+
+        #[zbus(signal)]
+        async fn action_invoked(
+            emitter: &SignalEmitter<'_>,
+            id: u32,
+            action_key: &str,
+        ) -> zbus::Result<()>;
+    }
+
+    async fn notification_server() -> (Connection, Connection, Sent) {
+        let (server, portal) = p2p_pair().await;
+        let mock = MockNotifications::default();
+        let sent = mock.sent.clone();
+        server
+            .object_server()
+            .at(NOTIFICATIONS_PATH, mock)
+            .await
+            .unwrap();
+
+        (server, portal, sent)
+    }
+
+    fn button(label: &str, action: &str, target: &str) -> HashMap<String, Value<'static>> {
+        HashMap::from([
+            ("label".to_string(), Value::from(label.to_string())),
+            ("action".to_string(), Value::from(action.to_string())),
+            ("target".to_string(), Value::from(target.to_string())),
+        ])
+    }
+
+    #[tokio::test]
+    async fn notifications_are_forwarded() {
+        let (_server, portal, sent) = notification_server().await;
+
+        let notification = HashMap::from([
+            ("title", Value::from("Download finished")),
+            ("body", Value::from("report.pdf")),
+            ("priority", Value::from("urgent")),
+            ("default-action", Value::from("app.open")),
+            (
+                "buttons",
+                Value::from(vec![button("Show", "app.show", "report.pdf")]),
+            ),
+        ]);
+
+        NotificationService::default()
+            .add_notification(&portal, "org.example.App", "download", notification)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *sent.lock().unwrap(),
+            [(
+                "Download finished".to_string(),
+                "report.pdf".to_string(),
+                ["default", "", "button-0", "Show"]
+                    .map(String::from)
+                    .to_vec(),
+                2
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn actions_are_passed_back_to_the_app() {
+        let (server, portal, _) = notification_server().await;
+        let mut messages = MessageStream::from(&server);
+
+        let notification = HashMap::from([
+            ("title", Value::from("Download finished")),
+            (
+                "buttons",
+                Value::from(vec![button("Show", "app.show", "report.pdf")]),
+            ),
+        ]);
+
+        NotificationService::default()
+            .add_notification(&portal, "org.example.App", "download", notification)
+            .await
+            .unwrap();
+
+        // give the watcher a moment to subscribe
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let emitter = SignalEmitter::new(&server, NOTIFICATIONS_PATH).unwrap();
+        MockNotifications::action_invoked(&emitter, 1, "button-0")
+            .await
+            .unwrap();
+
+        let message = tokio::time::timeout(Duration::from_secs(5), async {
+            while let Some(Ok(message)) = messages.next().await {
+                if message.message_type() == Type::Signal
+                    && message.header().interface().is_some_and(|interface| {
+                        interface == "org.freedesktop.impl.portal.Notification"
+                    })
+                {
+                    return message;
+                }
+            }
+            panic!("connection closed");
+        })
+        .await
+        .expect("no ActionInvoked signal");
+
+        let (app_id, id, action, parameter): (String, String, String, Vec<OwnedValue>) =
+            message.body().deserialize().unwrap();
+
+        assert_eq!(
+            (app_id.as_str(), id.as_str(), action.as_str()),
+            ("org.example.App", "download", "app.show")
+        );
+        assert_eq!(
+            parameter,
+            [OwnedValue::from(zvariant::Str::from("report.pdf"))]
+        );
+    }
+}
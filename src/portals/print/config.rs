@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use which::which;
+
+use crate::portals::{appchooser::config::RunnerType, dialog::validate_runner};
+
+#[derive(Debug, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "kebab-case")]
+#[schemars(deny_unknown_fields)]
+pub struct PrintConfig {
+    pub enabled: bool,
+    pub runner: Option<RunnerType>,
+    // name -> extra arguments for lp, e.g. "Two-sided" = ["-o", "sides=two-sided-long-edge"]
+    pub presets: Option<HashMap<String, Vec<String>>>,
+}
+
+impl PrintConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        problems.extend(validate_runner("print.runner", self.runner.as_ref()));
+
+        if self.enabled {
+            for command in ["lp", "lpstat"] {
+                if which(command).is_err() {
+                    problems.push(format!("print: command {:?} could not be found", command));
+                }
+            }
+        }
+
+        problems
+    }
+
+    // the configured presets sorted by name, or a few common ones
+    pub fn presets(&self) -> Vec<(String, Vec<String>)> {
+        let Some(presets) = &self.presets else {
+            return [
+                ("Default", vec![]),
+                (
+                    "Two-sided (long edge)",
+                    vec!["-o", "sides=two-sided-long-edge"],
+                ),
+                (
+                    "Two-sided (short edge)",
+                    vec!["-o", "sides=two-sided-short-edge"],
+                ),
+            ]
+            .into_iter()
+            .map(|(name, args)| {
+                (
+                    name.to_string(),
+                    args.into_iter().map(|arg| arg.to_string()).collect(),
+                )
+            })
+            .collect();
+        };
+
+        let mut presets: Vec<(String, Vec<String>)> = presets
+            .iter()
+            .map(|(name, args)| (name.clone(), args.clone()))
+            .collect();
+
+        presets.sort();
+        presets
+    }
+}
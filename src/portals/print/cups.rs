@@ -0,0 +1,101 @@
+use std::{os::fd::OwnedFd, process::Stdio};
+
+use tokio::process::Command;
+
+// the CUPS command line tools, tests swap them for stubs
+#[derive(Debug, Clone)]
+pub struct Cups {
+    pub lpstat: String,
+    pub lp: String,
+}
+
+impl Default for Cups {
+    fn default() -> Self {
+        Cups {
+            lpstat: "lpstat".to_string(),
+            lp: "lp".to_string(),
+        }
+    }
+}
+
+impl Cups {
+    // printer names as reported by `lpstat -e`
+    pub async fn printers(&self) -> std::io::Result<Vec<String>> {
+        let output = Command::new(&self.lpstat)
+            .arg("-e")
+            .stdin(Stdio::null())
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            return Err(std::io::Error::other(format!(
+                "lpstat failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect())
+    }
+
+    // the document is passed to lp on stdin, returns lp's output (e.g. "request id is ...")
+    pub async fn print(
+        &self,
+        printer: &str,
+        title: &str,
+        arguments: &[String],
+        document: OwnedFd,
+    ) -> std::io::Result<String> {
+        let output = Command::new(&self.lp)
+            .args(lp_arguments(printer, title, arguments))
+            .stdin(Stdio::from(document))
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            return Err(std::io::Error::other(format!(
+                "lp failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+pub fn lp_arguments(printer: &str, title: &str, arguments: &[String]) -> Vec<String> {
+    let mut args = vec!["-d".to_string(), printer.to_string()];
+
+    if !title.is_empty() {
+        args.extend(["-t".to_string(), title.to_string()]);
+    }
+
+    args.extend(arguments.iter().cloned());
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lp_arguments;
+
+    #[test]
+    fn lp_gets_the_printer_title_and_preset() {
+        let preset = ["-o", "sides=two-sided-long-edge"].map(String::from);
+
+        assert_eq!(
+            lp_arguments("office", "Report", &preset),
+            [
+                "-d",
+                "office",
+                "-t",
+                "Report",
+                "-o",
+                "sides=two-sided-long-edge"
+            ]
+        );
+        assert_eq!(lp_arguments("office", "", &[]), ["-d", "office"]);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod config;
+mod cups;
+pub mod service;
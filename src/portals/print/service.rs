@@ -0,0 +1,290 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Mutex,
+        atomic::{AtomicU32, Ordering},
+    },
+};
+
+use zbus::{fdo, interface};
+use zvariant::{ObjectPath, OwnedFd, OwnedValue, Value};
+
+use crate::portals::dialog::Runner;
+
+use super::{config::PrintConfig, cups::Cups};
+
+// what the user picked in PreparePrint, used by the following Print call
+#[derive(Debug, Clone)]
+struct PrintJob {
+    printer: String,
+    arguments: Vec<String>,
+}
+
+pub struct PrintService {
+    config: PrintConfig,
+    runner: Runner,
+    cups: Cups,
+    jobs: Mutex<HashMap<u32, PrintJob>>,
+    next_token: AtomicU32,
+}
+
+impl PrintService {
+    pub fn new(runner: Runner, config: PrintConfig) -> Self {
+        PrintService {
+            config,
+            runner,
+            cups: Cups::default(),
+            jobs: Mutex::new(HashMap::new()),
+            next_token: AtomicU32::new(1),
+        }
+    }
+}
+
+#[interface(name = "org.freedesktop.impl.portal.Print")]
+impl PrintService {
+    #[zbus(property, name = "version")]
+    async fn version(&self) -> u32 {
+        1
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn prepare_print(
+        &self,
+        handle: ObjectPath<'_>,
+        app_id: &str,
+        parent_window: &str,
+        title: &str,
+        settings: HashMap<&str, Value<'_>>,
+        page_setup: HashMap<&str, Value<'_>>,
+        options: HashMap<&str, Value<'_>>,
+    ) -> fdo::Result<(u32, HashMap<String, OwnedValue>)> {
+        tracing::debug!(
+            "PreparePrint called with handle: {:?}, app_id: {:?}, parent_window: {}, title: {:?}, settings: {:?}, page_setup: {:?}, options: {:?}",
+            handle,
+            app_id,
+            parent_window,
+            title,
+            settings,
+            page_setup,
+            options
+        );
+
+        let Some(job) = self.pick_job().await? else {
+            tracing::info!("PreparePrint was cancelled");
+            return Ok((1, HashMap::new()));
+        };
+
+        // hand the settings back with the selected printer, the app uses them to render the document
+        let mut settings = to_owned_dict(settings)?;
+        settings.insert(
+            "printer".to_string(),
+            zvariant::Str::from(job.printer.clone()).into(),
+        );
+
+        let token = self.next_token.fetch_add(1, Ordering::SeqCst);
+
+        tracing::info!("Prepared print job {}: {:?}", token, job);
+
+        self.jobs.lock().unwrap().insert(token, job);
+
+        let mut m = HashMap::new();
+        m.insert("settings".to_string(), to_owned_value(settings)?);
+        m.insert(
+            "page-setup".to_string(),
+            to_owned_value(to_owned_dict(page_setup)?)?,
+        );
+        m.insert("token".to_string(), OwnedValue::from(token));
+
+        Ok((0, m))
+    }
+
+    async fn print(
+        &self,
+        handle: ObjectPath<'_>,
+        app_id: &str,
+        parent_window: &str,
+        title: &str,
+        fd: OwnedFd,
+        options: HashMap<&str, Value<'_>>,
+    ) -> fdo::Result<(u32, HashMap<String, OwnedValue>)> {
+        tracing::debug!(
+            "Print called with handle: {:?}, app_id: {:?}, parent_window: {}, title: {:?}, options: {:?}",
+            handle,
+            app_id,
+            parent_window,
+            title,
+            options
+        );
+
+        let prepared = match options.get("token") {
+            Some(Value::U32(token)) => self.jobs.lock().unwrap().remove(token),
+            _ => None,
+        };
+
+        // apps can print without calling PreparePrint first
+        let job = match prepared {
+            Some(job) => job,
+            None => match self.pick_job().await? {
+                Some(job) => job,
+                None => {
+                    tracing::info!("Print was cancelled");
+                    return Ok((1, HashMap::new()));
+                }
+            },
+        };
+
+        let output = self
+            .cups
+            .print(&job.printer, title, &job.arguments, fd.into())
+            .await
+            .map_err(|err| fdo::Error::Failed(format!("zenzai: {}", err)))?;
+
+        tracing::info!("Printing {:?} on {}: {}", title, job.printer, output);
+
+        Ok((0, HashMap::new()))
+    }
+}
+
+impl PrintService {
+    // asks for the printer and then for a preset, None if the user cancelled
+    async fn pick_job(&self) -> fdo::Result<Option<PrintJob>> {
+        let printers = self
+            .cups
+            .printers()
+            .await
+            .map_err(|err| fdo::Error::Failed(format!("zenzai: {}", err)))?;
+
+        if printers.is_empty() {
+            return Err(fdo::Error::Failed(
+                "zenzai: no printers available".to_string(),
+            ));
+        }
+
+        let printer = if printers.len() == 1 {
+            printers.first().cloned()
+        } else {
+            let selection = self.runner.pick(&printers).await?;
+            selection.map(|i| printers[i].clone())
+        };
+
+        let Some(printer) = printer else {
+            return Ok(None);
+        };
+
+        let presets = self.config.presets();
+
+        let arguments = if presets.len() <= 1 {
+            Some(
+                presets
+                    .into_iter()
+                    .next()
+                    .map(|(_, args)| args)
+                    .unwrap_or_default(),
+            )
+        } else {
+            let names: Vec<String> = presets.iter().map(|(name, _)| name.clone()).collect();
+            let selection = self.runner.pick(&names).await?;
+
+            selection.map(|i| presets[i].1.clone())
+        };
+
+        Ok(arguments.map(|arguments| PrintJob { printer, arguments }))
+    }
+}
+
+fn to_owned_dict(dict: HashMap<&str, Value<'_>>) -> fdo::Result<HashMap<String, OwnedValue>> {
+    dict.into_iter()
+        .map(|(key, value)| Ok((key.to_string(), OwnedValue::try_from(value)?)))
+        .collect::<Result<_, zvariant::Error>>()
+        .map_err(|err| fdo::Error::InvalidArgs(err.to_string()))
+}
+
+fn to_owned_value(dict: HashMap<String, OwnedValue>) -> fdo::Result<OwnedValue> {
+    OwnedValue::try_from(Value::from(dict)).map_err(|err| fdo::Error::Failed(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        fs::{self, File},
+        os::unix::fs::PermissionsExt,
+        path::{Path, PathBuf},
+    };
+
+    use zvariant::{ObjectPath, OwnedFd};
+
+    use super::PrintService;
+    use crate::portals::{
+        appchooser::config::{Command, RunnerType},
+        dialog::Runner,
+        print::{config::PrintConfig, cups::Cups},
+    };
+
+    fn stub(dir: &Path, name: &str, script: &str) -> String {
+        let path = dir.join(name);
+        fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    fn test_dir() -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("zenzai-print-test-{:08x}", rand::random::<u32>()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn documents_are_sent_to_the_picked_printer() {
+        let dir = test_dir();
+        let log = dir.join("lp.log");
+
+        // the runner picks the second printer and the two-sided preset
+        let runner = Runner::new(
+            Some(RunnerType::Dmenu(Command {
+                command: "grep".to_string(),
+                arguments: Some(
+                    ["-x", "-e", "home", "-e", "Two-sided (long edge)"]
+                        .map(String::from)
+                        .to_vec(),
+                ),
+                ..Default::default()
+            })),
+            None,
+        );
+
+        let mut service = PrintService::new(runner, PrintConfig::default());
+        service.cups = Cups {
+            lpstat: stub(&dir, "lpstat", "printf 'office\\nhome\\n'"),
+            lp: stub(
+                &dir,
+                "lp",
+                &format!(
+                    "echo \"$@\" > {0}; cat >> {0}; echo request id is home-1",
+                    log.display()
+                ),
+            ),
+        };
+
+        let document = dir.join("document");
+        fs::write(&document, "%PDF").unwrap();
+        let fd = OwnedFd::from(std::os::fd::OwnedFd::from(File::open(&document).unwrap()));
+
+        let handle =
+            ObjectPath::from_static_str("/org/freedesktop/portal/desktop/request/1_1/t").unwrap();
+
+        let (response, _) = service
+            .print(handle, "org.example.App", "", "Report", fd, HashMap::new())
+            .await
+            .unwrap();
+
+        assert_eq!(response, 0);
+        assert_eq!(
+            fs::read_to_string(&log).unwrap(),
+            "-d home -t Report -o sides=two-sided-long-edge\n%PDF"
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
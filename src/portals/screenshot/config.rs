@@ -0,0 +1,33 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::utils::command::validate_command;
+
+#[derive(Debug, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "kebab-case")]
+#[schemars(deny_unknown_fields)]
+pub struct ScreenshotConfig {
+    pub enabled: bool,
+    pub capture: Option<Vec<String>>,
+    pub interactive: Option<Vec<String>>,
+    pub pick_color: Option<Vec<String>>,
+}
+
+impl ScreenshotConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        problems.extend(validate_command("screenshot.capture", &self.capture));
+        problems.extend(validate_command(
+            "screenshot.interactive",
+            &self.interactive,
+        ));
+        problems.extend(validate_command("screenshot.pick-color", &self.pick_color));
+
+        if self.enabled && self.capture.is_none() && self.interactive.is_none() {
+            problems.push("screenshot: neither capture nor interactive is set".to_string());
+        }
+
+        problems
+    }
+}
@@ -0,0 +1,281 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use zbus::{fdo, interface};
+use zvariant::{ObjectPath, OwnedValue, Value};
+
+use crate::utils::{command::run_template, uri::path_to_file_uri, xdg};
+
+use super::config::ScreenshotConfig;
+
+pub struct ScreenshotService {
+    pub config: ScreenshotConfig,
+}
+
+#[interface(name = "org.freedesktop.impl.portal.Screenshot")]
+impl ScreenshotService {
+    #[zbus(property, name = "version")]
+    async fn version(&self) -> u32 {
+        2
+    }
+
+    async fn screenshot(
+        &self,
+        handle: ObjectPath<'_>,
+        app_id: &str,
+        parent_window: &str,
+        options: HashMap<&str, Value<'_>>,
+    ) -> fdo::Result<(u32, HashMap<String, OwnedValue>)> {
+        tracing::debug!(
+            "Screenshot called with handle: {:?}, app_id: {:?}, parent_window: {}, options: {:?}",
+            handle,
+            app_id,
+            parent_window,
+            options
+        );
+
+        let interactive = bool_option(&options, "interactive", false);
+        let modal = bool_option(&options, "modal", true);
+
+        // fall back to the other command if only one of them is configured
+        let command = if interactive {
+            self.config
+                .interactive
+                .as_ref()
+                .or(self.config.capture.as_ref())
+        } else {
+            self.config
+                .capture
+                .as_ref()
+                .or(self.config.interactive.as_ref())
+        };
+
+        let Some(command) = command else {
+            return Err(fdo::Error::Failed(
+                "zenzai: no screenshot command configured".to_string(),
+            ));
+        };
+
+        let dir = xdg::private_runtime_dir("screenshots").map_err(|err| {
+            fdo::Error::Failed(format!(
+                "zenzai: could not create screenshot directory: {}",
+                err
+            ))
+        })?;
+
+        let output = dir.join(format!(
+            "zenzai-screenshot-{:08x}.png",
+            rand::random::<u32>()
+        ));
+
+        let placeholders = [
+            ("{output}", output.to_string_lossy().to_string()),
+            ("{app_id}", app_id.to_string()),
+            ("{parent_window}", parent_window.to_string()),
+            ("{interactive}", interactive.to_string()),
+            ("{modal}", modal.to_string()),
+        ];
+
+        let (success, _) = run(command, &placeholders).await?;
+
+        // most selection tools exit with an error when the selection was aborted
+        if !success || !is_non_empty_file(&output) {
+            tracing::info!("Screenshot was cancelled");
+            let _ = fs::remove_file(&output);
+            return Ok((1, HashMap::new()));
+        }
+
+        let uri = path_to_file_uri(&output);
+
+        tracing::info!("Screenshot saved to {}", uri);
+
+        let mut m = HashMap::new();
+        m.insert("uri".to_string(), zvariant::Str::from(uri).into());
+
+        Ok((0, m))
+    }
+
+    async fn pick_color(
+        &self,
+        handle: ObjectPath<'_>,
+        app_id: &str,
+        parent_window: &str,
+        options: HashMap<&str, Value<'_>>,
+    ) -> fdo::Result<(u32, HashMap<String, OwnedValue>)> {
+        tracing::debug!(
+            "PickColor called with handle: {:?}, app_id: {:?}, parent_window: {}, options: {:?}",
+            handle,
+            app_id,
+            parent_window,
+            options
+        );
+
+        let Some(command) = &self.config.pick_color else {
+            return Err(fdo::Error::Failed(
+                "zenzai: no pick-color command configured".to_string(),
+            ));
+        };
+
+        let placeholders = [
+            ("{app_id}", app_id.to_string()),
+            ("{parent_window}", parent_window.to_string()),
+        ];
+
+        let (success, stdout) = run(command, &placeholders).await?;
+
+        if !success || stdout.trim().is_empty() {
+            tracing::info!("PickColor was cancelled");
+            return Ok((1, HashMap::new()));
+        }
+
+        let Some(color) = parse_color(stdout.trim()) else {
+            return Err(fdo::Error::Failed(format!(
+                "zenzai: could not parse color {:?}",
+                stdout.trim()
+            )));
+        };
+
+        tracing::info!("Picked color: {:?}", color);
+
+        let color = OwnedValue::try_from(Value::from(color))
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+
+        let mut m = HashMap::new();
+        m.insert("color".to_string(), color);
+
+        Ok((0, m))
+    }
+}
+
+fn bool_option(options: &HashMap<&str, Value<'_>>, key: &str, default: bool) -> bool {
+    match options.get(key) {
+        Some(Value::Bool(value)) => *value,
+        _ => default,
+    }
+}
+
+fn is_non_empty_file(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|meta| meta.is_file() && meta.len() > 0)
+}
+
+// runs the command with its placeholders replaced, returns whether it succeeded and its stdout
+async fn run(command: &[String], placeholders: &[(&str, String)]) -> fdo::Result<(bool, String)> {
+    let output = run_template(command, placeholders)
+        .await
+        .map_err(|err| fdo::Error::Failed(format!("zenzai: {}", err)))?;
+
+    let stdout = String::from_utf8(output.stdout)
+        .map_err(|err| fdo::Error::Failed(format!("zenzai: {}", err)))?;
+
+    Ok((output.status.success(), stdout))
+}
+
+// accepts "r g b" / "r, g, b" with values from 0-255 (e.g. hyprpicker -f rgb) or any css color
+fn parse_color(color: &str) -> Option<(f64, f64, f64)> {
+    let parts: Vec<&str> = color
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|part| !part.is_empty())
+        .collect();
+
+    if let [r, g, b] = parts.as_slice()
+        && let (Ok(r), Ok(g), Ok(b)) = (r.parse::<u8>(), g.parse::<u8>(), b.parse::<u8>())
+    {
+        return Some((r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0));
+    }
+
+    let [r, g, b, _] = csscolorparser::parse(color).ok()?.to_array();
+    Some((r.into(), g.into(), b.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use zvariant::{ObjectPath, Value};
+
+    use super::{ScreenshotConfig, ScreenshotService, parse_color};
+    use crate::utils::uri::file_uri_to_path;
+
+    fn service(capture: &str, pick_color: &str) -> ScreenshotService {
+        let sh = |script: &str| {
+            ["sh", "-c", script, "sh", "{output}"]
+                .map(|arg| arg.to_string())
+                .to_vec()
+        };
+
+        ScreenshotService {
+            config: ScreenshotConfig {
+                enabled: true,
+                capture: Some(sh(capture)),
+                interactive: None,
+                pick_color: Some(sh(pick_color)),
+            },
+        }
+    }
+
+    fn handle() -> ObjectPath<'static> {
+        ObjectPath::from_static_str("/org/freedesktop/portal/desktop/request/1_1/t").unwrap()
+    }
+
+    // css colors are parsed as f32
+    fn parse_rgb8(color: &str) -> Option<[u8; 3]> {
+        let (r, g, b) = parse_color(color)?;
+        Some([r, g, b].map(|c| (c * 255.0).round() as u8))
+    }
+
+    #[test]
+    fn colors_are_parsed() {
+        assert_eq!(parse_color("255 0 51"), Some((1.0, 0.0, 0.2)));
+        assert_eq!(parse_color("255, 0, 51"), Some((1.0, 0.0, 0.2)));
+        assert_eq!(parse_rgb8("#ff0033"), Some([255, 0, 51]));
+        assert_eq!(parse_rgb8("rgb(255 0 51)"), Some([255, 0, 51]));
+        assert_eq!(parse_rgb8("red"), Some([255, 0, 0]));
+        assert_eq!(parse_color("256 0 0"), None);
+        assert_eq!(parse_color("not a color"), None);
+    }
+
+    #[tokio::test]
+    async fn screenshot_returns_the_written_file() {
+        let service = service("printf png > \"$1\"", "");
+
+        let (response, results) = service
+            .screenshot(handle(), "org.example.App", "", HashMap::new())
+            .await
+            .unwrap();
+
+        assert_eq!(response, 0);
+
+        let Some(Value::Str(uri)) = results.get("uri").map(|uri| &**uri) else {
+            panic!("no uri in {:?}", results);
+        };
+        let path = file_uri_to_path(uri).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "png");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_or_empty_screenshots_are_cancelled() {
+        for capture in ["exit 1", "true"] {
+            let (response, _) = service(capture, "")
+                .screenshot(handle(), "org.example.App", "", HashMap::new())
+                .await
+                .unwrap();
+
+            assert_eq!(response, 1, "{:?} was not cancelled", capture);
+        }
+    }
+
+    #[tokio::test]
+    async fn pick_color_parses_the_command_output() {
+        let (response, results) = service("", "echo 255 0 51")
+            .pick_color(handle(), "org.example.App", "", HashMap::new())
+            .await
+            .unwrap();
+
+        assert_eq!(response, 0);
+        assert_eq!(
+            results.get("color").map(|color| &**color),
+            Some(&Value::from((1.0, 0.0, 0.2)))
+        );
+    }
+}
@@ -1,11 +1,13 @@
 use std::collections::HashMap;
 
+use schemars::JsonSchema;
 use serde::Deserialize;
 
 pub type SettingsMap = HashMap<String, HashMap<String, SettingsMapValue>>;
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, JsonSchema, Default)]
 #[serde(rename_all = "kebab-case")]
+#[schemars(deny_unknown_fields)]
 pub struct SettingsConfig {
     pub enabled: bool,
     pub color_scheme: Option<ColorScheme>,
@@ -14,7 +16,24 @@ pub struct SettingsConfig {
     pub dict: Option<SettingsMap>,
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+impl SettingsConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if let Some(AccentColor::ColorString(color)) = &self.accent_color
+            && csscolorparser::parse(color).is_err()
+        {
+            problems.push(format!(
+                "settings.accent-color: could not parse color {:?}",
+                color
+            ));
+        }
+
+        problems
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub enum ColorScheme {
     #[default]
@@ -23,24 +42,25 @@ pub enum ColorScheme {
     Light,
 }
 
-impl Into<u32> for ColorScheme {
-    fn into(self) -> u32 {
-        match self {
-            Self::NoPreference => 0,
-            Self::Dark => 1,
-            Self::Light => 2,
+impl From<ColorScheme> for u32 {
+    fn from(val: ColorScheme) -> Self {
+        match val {
+            ColorScheme::NoPreference => 0,
+            ColorScheme::Dark => 1,
+            ColorScheme::Light => 2,
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Clone)]
 #[serde(untagged)]
 pub enum AccentColor {
     ColorString(String),
-    RGB(ColorRGB),
+    Rgb(ColorRGB),
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Clone)]
+#[schemars(deny_unknown_fields)]
 pub struct ColorRGB {
     r: u8,
     g: u8,
@@ -58,16 +78,16 @@ impl AccentColor {
                     None
                 }
             }
-            AccentColor::RGB(ColorRGB { r, g, b }) => Some((
-                (r.clone() as f64) / 255.0,
-                (g.clone() as f64) / 255.0,
-                (b.clone() as f64) / 255.0,
+            AccentColor::Rgb(ColorRGB { r, g, b }) => Some((
+                (*r as f64) / 255.0,
+                (*g as f64) / 255.0,
+                (*b as f64) / 255.0,
             )),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub enum Contrast {
     #[default]
@@ -75,16 +95,16 @@ pub enum Contrast {
     High,
 }
 
-impl Into<u32> for Contrast {
-    fn into(self) -> u32 {
-        match self {
-            Self::NoPreference => 0,
-            Self::High => 1,
+impl From<Contrast> for u32 {
+    fn from(val: Contrast) -> Self {
+        match val {
+            Contrast::NoPreference => 0,
+            Contrast::High => 1,
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Clone)]
 #[serde(untagged)]
 pub enum SettingsMapValue {
     String(String),
@@ -1,4 +1,4 @@
-pub const NAMESPACE: &'static str = "org.freedesktop.appearance";
-pub const KEY_COLOR_SCHEME: &'static str = "color-scheme";
-pub const KEY_CONTRAST: &'static str = "contrast";
-pub const KEY_ACCENT_COLOR: &'static str = "accent-color";
+pub const NAMESPACE: &str = "org.freedesktop.appearance";
+pub const KEY_COLOR_SCHEME: &str = "color-scheme";
+pub const KEY_CONTRAST: &str = "contrast";
+pub const KEY_ACCENT_COLOR: &str = "accent-color";
@@ -3,10 +3,10 @@ use std::collections::HashMap;
 use zbus::{fdo, interface, object_server::SignalEmitter};
 use zvariant::Value;
 
-use crate::portals::settings::config::{Contrast, SettingsMapValue};
+use crate::portals::settings::config::SettingsMapValue;
 use crate::utils::hashmap::wildcard_get_all;
 
-use super::config::{ColorScheme, SettingsConfig};
+use super::config::SettingsConfig;
 
 use super::constants::{KEY_ACCENT_COLOR, KEY_COLOR_SCHEME, KEY_CONTRAST, NAMESPACE};
 
@@ -49,7 +49,9 @@ impl SettingsService {
                     )),
                     Some(color) => Ok(color.into()),
                 })
-                .unwrap(),
+                .unwrap_or(Err(fdo::Error::Failed(
+                    "zenzai: accent-color is unset".to_string(),
+                ))),
             _ => Err(fdo::Error::Failed(format!(
                 "zenzai: unknown key: {}.{}",
                 namespace, key
@@ -78,9 +80,9 @@ impl SettingsService {
                     k.clone(),
                     match v {
                         SettingsMapValue::String(str) => zvariant::Value::Str(str.into()),
-                        SettingsMapValue::Int(int) => zvariant::Value::I64(int.clone()),
-                        SettingsMapValue::Bool(b) => zvariant::Value::Bool(b.clone()),
-                        SettingsMapValue::Float(f) => zvariant::Value::F64(f.clone()),
+                        SettingsMapValue::Int(int) => zvariant::Value::I64(int),
+                        SettingsMapValue::Bool(b) => zvariant::Value::Bool(b),
+                        SettingsMapValue::Float(f) => zvariant::Value::F64(f),
                     },
                 );
             }
@@ -90,26 +92,22 @@ impl SettingsService {
 
         let mut nsmap = HashMap::new();
 
-        let color_scheme = self
-            .config
-            .color_scheme
-            .clone()
-            .unwrap_or(ColorScheme::default());
+        let color_scheme = self.config.color_scheme.clone().unwrap_or_default();
         nsmap.insert(
             KEY_COLOR_SCHEME.to_string(),
             Value::U32(color_scheme.clone().into()),
         );
 
-        let contrast = self.config.contrast.clone().unwrap_or(Contrast::default());
+        let contrast = self.config.contrast.clone().unwrap_or_default();
         nsmap.insert(
             KEY_CONTRAST.to_string(),
             Value::U32(contrast.clone().into()),
         );
 
-        if let Some(color) = &self.config.accent_color {
-            if let Some(color) = color.to_color_tuple() {
-                nsmap.insert(KEY_ACCENT_COLOR.to_string(), color.into());
-            }
+        if let Some(color) = &self.config.accent_color
+            && let Some(color) = color.to_color_tuple()
+        {
+            nsmap.insert(KEY_ACCENT_COLOR.to_string(), color.into());
         }
 
         m.insert(NAMESPACE.to_string(), nsmap);
@@ -118,7 +116,7 @@ impl SettingsService {
 
         // no namespaces set == get all data
         if namespaces.is_empty() {
-            return Ok(m.into());
+            return Ok(m);
         }
 
         // execute the actual query
@@ -130,7 +128,7 @@ impl SettingsService {
             }
         }
 
-        Ok(resmap.into())
+        Ok(resmap)
     }
 
     #[zbus(signal)]
@@ -0,0 +1,39 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::{
+    portals::{appchooser::config::RunnerType, dialog::validate_runner},
+    utils::command::validate_command,
+};
+
+#[derive(Debug, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "kebab-case")]
+#[schemars(deny_unknown_fields)]
+pub struct WallpaperConfig {
+    pub enabled: bool,
+    pub background: Option<Vec<String>>,
+    pub lockscreen: Option<Vec<String>>,
+    // shown while asking for confirmation, e.g. ["imv", "{path}"]
+    pub preview: Option<Vec<String>>,
+    // always ask, not only when the app requests a preview
+    pub confirm: Option<bool>,
+    pub runner: Option<RunnerType>,
+}
+
+impl WallpaperConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        problems.extend(validate_command("wallpaper.background", &self.background));
+        problems.extend(validate_command("wallpaper.lockscreen", &self.lockscreen));
+        problems.extend(validate_command("wallpaper.preview", &self.preview));
+
+        problems.extend(validate_runner("wallpaper.runner", self.runner.as_ref()));
+
+        if self.enabled && self.background.is_none() && self.lockscreen.is_none() {
+            problems.push("wallpaper: neither background nor lockscreen is set".to_string());
+        }
+
+        problems
+    }
+}
@@ -0,0 +1,313 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use zbus::{fdo, interface};
+use zvariant::{ObjectPath, Value};
+
+use crate::{
+    constants::CONFIG_APP_NAME,
+    portals::dialog::Runner,
+    utils::{
+        command::run_template,
+        uri::{file_uri_to_path, path_to_file_uri},
+        xdg,
+    },
+};
+
+use super::config::WallpaperConfig;
+
+pub struct WallpaperService {
+    config: WallpaperConfig,
+    runner: Runner,
+    // where wallpapers are copied to
+    dir: PathBuf,
+}
+
+impl WallpaperService {
+    pub fn new(runner: Runner, config: WallpaperConfig) -> Self {
+        WallpaperService {
+            config,
+            runner,
+            dir: xdg::data_home().join(CONFIG_APP_NAME).join("wallpapers"),
+        }
+    }
+}
+
+#[interface(name = "org.freedesktop.impl.portal.Wallpaper")]
+impl WallpaperService {
+    #[zbus(property, name = "version")]
+    async fn version(&self) -> u32 {
+        1
+    }
+
+    #[zbus(name = "SetWallpaperURI")]
+    async fn set_wallpaper_uri(
+        &self,
+        handle: ObjectPath<'_>,
+        app_id: &str,
+        parent_window: &str,
+        uri: &str,
+        options: HashMap<&str, Value<'_>>,
+    ) -> fdo::Result<u32> {
+        tracing::debug!(
+            "SetWallpaperURI called with handle: {:?}, app_id: {:?}, parent_window: {}, uri: {}, options: {:?}",
+            handle,
+            app_id,
+            parent_window,
+            uri,
+            options
+        );
+
+        let show_preview = matches!(options.get("show-preview"), Some(Value::Bool(true)));
+        let set_on = match options.get("set-on") {
+            Some(Value::Str(set_on)) => set_on.to_string(),
+            _ => "both".to_string(),
+        };
+
+        let Some(source) = file_uri_to_path(uri) else {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "zenzai: not a local file: {}",
+                uri
+            )));
+        };
+
+        if (show_preview || self.config.confirm.unwrap_or(false)) && !self.confirm(&source).await? {
+            tracing::info!("Setting the wallpaper was cancelled");
+            return Ok(1);
+        }
+
+        // the uri usually points into the app's sandbox which might go away
+        let path = copy_to_wallpaper_dir(&source, &self.dir).map_err(|err| {
+            fdo::Error::Failed(format!("zenzai: could not copy wallpaper: {}", err))
+        })?;
+
+        let commands: Vec<&Vec<String>> = match set_on.as_str() {
+            "background" => vec![&self.config.background],
+            "lockscreen" => vec![&self.config.lockscreen],
+            _ => vec![&self.config.background, &self.config.lockscreen],
+        }
+        .into_iter()
+        .flatten()
+        .collect();
+
+        if commands.is_empty() {
+            return Err(fdo::Error::Failed(format!(
+                "zenzai: no wallpaper command configured for {:?}",
+                set_on
+            )));
+        }
+
+        let placeholders = [
+            ("{path}", path.to_string_lossy().to_string()),
+            ("{uri}", path_to_file_uri(&path)),
+            ("{app_id}", app_id.to_string()),
+        ];
+
+        for command in commands {
+            tracing::info!("Setting wallpaper: {:?}", command);
+
+            let output = run_template(command, &placeholders)
+                .await
+                .map_err(|err| fdo::Error::Failed(format!("zenzai: {}", err)))?;
+
+            if !output.status.success() {
+                return Err(fdo::Error::Failed(format!(
+                    "zenzai: {} exited with {}",
+                    command[0], output.status
+                )));
+            }
+        }
+
+        Ok(0)
+    }
+}
+
+impl WallpaperService {
+    // shows the preview command (if any) while asking via the runner
+    async fn confirm(&self, path: &Path) -> fdo::Result<bool> {
+        if !self.runner.is_set() {
+            tracing::warn!("No runner configured, setting the wallpaper without asking");
+            return Ok(true);
+        };
+
+        // aborting the task kills the preview
+        let preview = self.config.preview.clone().map(|preview| {
+            let placeholders = [("{path}", path.to_string_lossy().to_string())];
+
+            tokio::spawn(async move {
+                if let Err(err) = run_template(&preview, &placeholders).await {
+                    tracing::error!("Preview failed: {}", err);
+                }
+            })
+        });
+
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let confirmed = self
+            .runner
+            .confirm(&format!("Set {} as wallpaper", name))
+            .await;
+
+        if let Some(preview) = preview {
+            preview.abort();
+        }
+
+        confirmed
+    }
+}
+
+fn copy_to_wallpaper_dir(source: &Path, dir: &Path) -> std::io::Result<PathBuf> {
+    let name = source
+        .file_name()
+        .ok_or(std::io::Error::other("wallpaper has no file name"))?;
+    let path = dir.join(name);
+
+    if source != path {
+        fs::create_dir_all(dir)?;
+        fs::copy(source, &path)?;
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use zvariant::{ObjectPath, Value};
+
+    use super::{WallpaperConfig, WallpaperService};
+    use crate::{
+        portals::{
+            appchooser::config::{Command, RunnerType},
+            dialog::Runner,
+        },
+        utils::uri::path_to_file_uri,
+    };
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "zenzai-wallpaper-test-{:08x}",
+            rand::random::<u32>()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // writes {path} to `output`
+    fn stub(output: &Path) -> Option<Vec<String>> {
+        Some(
+            ["sh", "-c", "printf %s \"$1\" > \"$2\"", "sh", "{path}"]
+                .map(String::from)
+                .into_iter()
+                .chain([output.to_string_lossy().to_string()])
+                .collect(),
+        )
+    }
+
+    // a runner that always selects the lines matching `pattern`
+    fn runner(pattern: &str) -> Runner {
+        Runner::new(
+            Some(RunnerType::Dmenu(Command {
+                command: "grep".to_string(),
+                arguments: Some(vec!["-x".to_string(), pattern.to_string()]),
+                ..Default::default()
+            })),
+            None,
+        )
+    }
+
+    fn handle() -> ObjectPath<'static> {
+        ObjectPath::from_static_str("/org/freedesktop/portal/desktop/request/1_1/t").unwrap()
+    }
+
+    #[tokio::test]
+    async fn the_command_for_set_on_gets_the_copied_file() {
+        let tmp = temp_dir();
+        let source = tmp.join("source").join("My Wallpaper.png");
+        fs::create_dir_all(source.parent().unwrap()).unwrap();
+        fs::write(&source, "png").unwrap();
+
+        let mut service = WallpaperService::new(
+            runner("Set My Wallpaper.png as wallpaper? yes"),
+            WallpaperConfig {
+                enabled: true,
+                background: stub(&tmp.join("background")),
+                lockscreen: stub(&tmp.join("lockscreen")),
+                confirm: Some(true),
+                ..Default::default()
+            },
+        );
+        service.dir = tmp.join("wallpapers");
+
+        let options = HashMap::from([("set-on", Value::from("background"))]);
+        let response = service
+            .set_wallpaper_uri(
+                handle(),
+                "org.example.App",
+                "",
+                &path_to_file_uri(&source),
+                options,
+            )
+            .await
+            .unwrap();
+
+        let copy = tmp.join("wallpapers").join("My Wallpaper.png");
+
+        assert_eq!(response, 0);
+        assert_eq!(
+            fs::read_to_string(tmp.join("background")).unwrap(),
+            copy.to_string_lossy()
+        );
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "png");
+        assert!(!tmp.join("lockscreen").exists());
+
+        fs::remove_dir_all(tmp).unwrap();
+    }
+
+    #[tokio::test]
+    async fn declining_the_preview_sets_nothing() {
+        let tmp = temp_dir();
+        let source = tmp.join("a.png");
+        fs::write(&source, "png").unwrap();
+
+        let mut service = WallpaperService::new(
+            runner("Set a.png as wallpaper? no"),
+            WallpaperConfig {
+                enabled: true,
+                background: stub(&tmp.join("background")),
+                preview: Some(vec!["sleep".to_string(), "10".to_string()]),
+                ..Default::default()
+            },
+        );
+        service.dir = tmp.join("wallpapers");
+
+        let options = HashMap::from([("show-preview", Value::from(true))]);
+        let response = service
+            .set_wallpaper_uri(
+                handle(),
+                "org.example.App",
+                "",
+                &path_to_file_uri(&source),
+                options,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response, 1);
+        assert!(!tmp.join("background").exists());
+        assert!(!tmp.join("wallpapers").exists());
+
+        fs::remove_dir_all(tmp).unwrap();
+    }
+}
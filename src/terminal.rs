@@ -1,51 +1,481 @@
-use std::{path::PathBuf, str::FromStr};
+use std::{env, fmt::Display, fs, path::PathBuf, str::FromStr};
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use which::which;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+use crate::utils::xdg;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
+#[allow(clippy::enum_variant_names)]
 pub enum Terminal {
     Alacritty,
     Foot,
+    Footclient,
     Ghostty,
     Kitty,
     Xterm,
-    Custom(String),
+    Wezterm,
+    Konsole,
+    GnomeTerminal,
+    GnomeConsole,
+    St,
+    Urxvt,
+    Tilix,
+    Rio,
+    XdgTerminalExec,
+    Custom(CustomTerminal),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(untagged)]
+pub enum CustomTerminal {
+    Command(String),
+    Config(CustomTerminalConfig),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+#[schemars(deny_unknown_fields)]
+pub struct CustomTerminalConfig {
+    pub command: String,
+    pub exec_flag: Option<String>,
+    pub class_flag: Option<String>,
+    pub title_flag: Option<String>,
+    pub arguments: Option<Vec<String>>,
+}
+
+impl CustomTerminal {
+    fn command(&self) -> &String {
+        match self {
+            CustomTerminal::Command(command) => command,
+            CustomTerminal::Config(config) => &config.command,
+        }
+    }
 }
 
 pub fn command_name(term: &Terminal) -> Option<String> {
     match term {
-        Terminal::Alacritty => String::from_str("alacritty").map_or(None, Some),
-        Terminal::Ghostty => String::from_str("ghostty").map_or(None, Some),
-        Terminal::Kitty => String::from_str("kitty").map_or(None, Some),
-        Terminal::Xterm => String::from_str("xterm").map_or(None, Some),
-        Terminal::Foot => String::from_str("foot").map_or(None, Some),
-        Terminal::Custom(term) => which(term).map(|_| Some(term.clone())).unwrap_or(None),
+        Terminal::Alacritty => String::from_str("alacritty").ok(),
+        Terminal::Ghostty => String::from_str("ghostty").ok(),
+        Terminal::Kitty => String::from_str("kitty").ok(),
+        Terminal::Xterm => String::from_str("xterm").ok(),
+        Terminal::Foot => String::from_str("foot").ok(),
+        Terminal::Footclient => String::from_str("footclient").ok(),
+        Terminal::Wezterm => String::from_str("wezterm").ok(),
+        Terminal::Konsole => String::from_str("konsole").ok(),
+        Terminal::GnomeTerminal => String::from_str("gnome-terminal").ok(),
+        Terminal::GnomeConsole => String::from_str("kgx").ok(),
+        Terminal::St => String::from_str("st").ok(),
+        Terminal::Urxvt => String::from_str("urxvt").ok(),
+        Terminal::Tilix => String::from_str("tilix").ok(),
+        Terminal::Rio => String::from_str("rio").ok(),
+        Terminal::XdgTerminalExec => String::from_str("xdg-terminal-exec").ok(),
+        Terminal::Custom(term) => which(term.command())
+            .map(|_| Some(term.command().clone()))
+            .unwrap_or(None),
     }
 }
 
 pub fn command_path(term: &Terminal) -> Option<PathBuf> {
     let name = command_name(term)?;
-    which(name).map_or(None, Some)
+    which(name).ok()
+}
+
+fn flag(name: &str, value: Option<&str>) -> Vec<String> {
+    value
+        .map(|value| vec![name.to_string(), value.to_string()])
+        .unwrap_or_default()
+}
+
+fn joined_flag(name: &str, value: Option<&str>) -> Vec<String> {
+    value
+        .map(|value| vec![format!("{}={}", name, value)])
+        .unwrap_or_default()
+}
+
+// flags that set the window class/app-id and title so compositor window rules can match
+fn window_arguments(term: &Terminal, class: Option<&str>, title: Option<&str>) -> Vec<String> {
+    match term {
+        Terminal::Alacritty | Terminal::Kitty => {
+            [flag("--class", class), flag("--title", title)].concat()
+        }
+        Terminal::Foot | Terminal::Footclient | Terminal::XdgTerminalExec => [
+            joined_flag("--app-id", class),
+            joined_flag("--title", title),
+        ]
+        .concat(),
+        // ghostty only accepts valid GTK application ids as class
+        Terminal::Ghostty => [
+            joined_flag("--class", class.filter(|class| class.contains('.'))),
+            joined_flag("--title", title),
+        ]
+        .concat(),
+        Terminal::Xterm => [flag("-class", class), flag("-T", title)].concat(),
+        Terminal::Wezterm => flag("--class", class),
+        Terminal::GnomeConsole => flag("--title", title),
+        Terminal::St => [flag("-c", class), flag("-t", title)].concat(),
+        Terminal::Urxvt => [flag("-name", class), flag("-title", title)].concat(),
+        Terminal::Tilix => flag("--title", title),
+        Terminal::Konsole | Terminal::GnomeTerminal | Terminal::Rio => vec![],
+        Terminal::Custom(CustomTerminal::Command(_)) => vec![],
+        Terminal::Custom(CustomTerminal::Config(config)) => [
+            config
+                .class_flag
+                .as_ref()
+                .map(|name| flag(name, class))
+                .unwrap_or_default(),
+            config
+                .title_flag
+                .as_ref()
+                .map(|name| flag(name, title))
+                .unwrap_or_default(),
+        ]
+        .concat(),
+    }
+}
+
+// builds the terminal arguments needed to run command inside of it
+pub fn exec_arguments(
+    term: &Terminal,
+    command: &str,
+    arguments: &[String],
+    class: Option<&str>,
+    title: Option<&str>,
+) -> Vec<String> {
+    let mut cmdline = vec![command.to_string()];
+    cmdline.extend(arguments.iter().cloned());
+
+    let window = window_arguments(term, class, title);
+
+    let prefix: Vec<String> = match term {
+        Terminal::Foot | Terminal::Footclient | Terminal::Kitty | Terminal::XdgTerminalExec => {
+            window
+        }
+        Terminal::Wezterm => [vec!["start".to_string()], window, vec!["--".to_string()]].concat(),
+        Terminal::GnomeTerminal | Terminal::GnomeConsole => {
+            [window, vec!["--".to_string()]].concat()
+        }
+        // tilix expects the whole command line as a single argument
+        Terminal::Tilix => {
+            return [window, vec!["-e".to_string(), shell_join(&cmdline)]].concat();
+        }
+        Terminal::Alacritty
+        | Terminal::Ghostty
+        | Terminal::Xterm
+        | Terminal::Konsole
+        | Terminal::St
+        | Terminal::Urxvt
+        | Terminal::Rio => [window, vec!["-e".to_string()]].concat(),
+        Terminal::Custom(CustomTerminal::Command(_)) => vec!["-e".to_string()],
+        Terminal::Custom(CustomTerminal::Config(config)) => {
+            let mut prefix = config.arguments.clone().unwrap_or_default();
+            prefix.extend(window);
+            let exec_flag = config.exec_flag.clone().unwrap_or("-e".to_string());
+
+            if !exec_flag.is_empty() {
+                prefix.push(exec_flag);
+            }
+
+            prefix
+        }
+    };
+
+    prefix.into_iter().chain(cmdline).collect()
+}
+
+fn shell_join(arguments: &[String]) -> String {
+    arguments
+        .iter()
+        .map(|arg| {
+            if !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c))
+            {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', "'\\''"))
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[derive(Debug)]
+pub enum TerminalSource {
+    Config,
+    XdgTerminalsList(PathBuf),
+    XdgTerminalExec,
+    EnvVar,
+    Probe,
+}
+
+impl Display for TerminalSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TerminalSource::Config => write!(f, "config"),
+            TerminalSource::XdgTerminalsList(path) => write!(f, "{}", path.display()),
+            TerminalSource::XdgTerminalExec => write!(f, "xdg-terminal-exec"),
+            TerminalSource::EnvVar => write!(f, "$TERMINAL"),
+            TerminalSource::Probe => write!(f, "probing"),
+        }
+    }
+}
+
+// config -> xdg-terminals.list -> xdg-terminal-exec -> $TERMINAL -> probing
+pub fn discover_terminal(configured: Option<Terminal>) -> Option<(Terminal, TerminalSource)> {
+    if let Some(term) = configured {
+        if command_path(&term).is_some() {
+            return Some((term, TerminalSource::Config));
+        }
+
+        tracing::warn!("Could not find configured terminal: {:?}", term);
+    }
+
+    let (listed, excluded) = terminal_from_xdg_terminals_list();
+
+    if let Some((term, path)) = listed {
+        return Some((term, TerminalSource::XdgTerminalsList(path)));
+    }
+
+    if command_path(&Terminal::XdgTerminalExec).is_some() {
+        return Some((Terminal::XdgTerminalExec, TerminalSource::XdgTerminalExec));
+    }
+
+    if let Some(term) = terminal_from_env_var() {
+        return Some((term, TerminalSource::EnvVar));
+    }
+
+    terminal_from_env(&excluded).map(|term| (term, TerminalSource::Probe))
+}
+
+fn terminal_from_command_name(name: &str) -> Option<Terminal> {
+    match name {
+        "alacritty" => Some(Terminal::Alacritty),
+        "foot" => Some(Terminal::Foot),
+        "footclient" => Some(Terminal::Footclient),
+        "ghostty" => Some(Terminal::Ghostty),
+        "kitty" => Some(Terminal::Kitty),
+        "xterm" => Some(Terminal::Xterm),
+        "wezterm" => Some(Terminal::Wezterm),
+        "konsole" => Some(Terminal::Konsole),
+        "gnome-terminal" => Some(Terminal::GnomeTerminal),
+        "kgx" => Some(Terminal::GnomeConsole),
+        "st" => Some(Terminal::St),
+        "urxvt" => Some(Terminal::Urxvt),
+        "tilix" => Some(Terminal::Tilix),
+        "rio" => Some(Terminal::Rio),
+        "xdg-terminal-exec" => Some(Terminal::XdgTerminalExec),
+        _ => None,
+    }
+}
+
+fn terminal_from_desktop_id(id: &str) -> Option<Terminal> {
+    match id.trim_end_matches(".desktop") {
+        "Alacritty" | "alacritty" => Some(Terminal::Alacritty),
+        "foot" | "org.codeberg.dnkl.foot" => Some(Terminal::Foot),
+        "footclient" | "org.codeberg.dnkl.footclient" => Some(Terminal::Footclient),
+        "com.mitchellh.ghostty" | "ghostty" => Some(Terminal::Ghostty),
+        "kitty" => Some(Terminal::Kitty),
+        "xterm" | "debian-xterm" => Some(Terminal::Xterm),
+        "org.wezfurlong.wezterm" | "wezterm" => Some(Terminal::Wezterm),
+        "org.kde.konsole" => Some(Terminal::Konsole),
+        "org.gnome.Terminal" => Some(Terminal::GnomeTerminal),
+        "org.gnome.Console" => Some(Terminal::GnomeConsole),
+        "st" | "st-256color" => Some(Terminal::St),
+        "rxvt-unicode" | "urxvt" => Some(Terminal::Urxvt),
+        "com.gexperts.Tilix" => Some(Terminal::Tilix),
+        "rio" | "com.raphaelamorim.rio" => Some(Terminal::Rio),
+        _ => None,
+    }
+}
+
+fn xdg_terminals_lists() -> Vec<PathBuf> {
+    let mut names: Vec<String> = xdg::current_desktops()
+        .iter()
+        .map(|desktop| format!("{}-xdg-terminals.list", desktop.to_lowercase()))
+        .collect();
+    names.push("xdg-terminals.list".to_string());
+
+    let config_dirs = [vec![xdg::config_home()], xdg::config_dirs()].concat();
+    let data_dirs: Vec<PathBuf> = [vec![xdg::data_home()], xdg::data_dirs()]
+        .concat()
+        .into_iter()
+        .map(|dir| dir.join("xdg-terminal-exec"))
+        .collect();
+
+    config_dirs
+        .iter()
+        .chain(data_dirs.iter())
+        .flat_map(|dir| names.iter().map(|name| dir.join(name)))
+        .filter(|path| path.is_file())
+        .collect()
 }
 
-pub fn terminal_from_env() -> Terminal {
-    if let Ok(_) = which("ghostty") {
-        return Terminal::Ghostty;
+// the desktop ids listed in a xdg-terminals.list and the ones excluded with a leading "-"
+fn parse_xdg_terminals_list(data: &str) -> (Vec<String>, Vec<String>) {
+    let mut listed = Vec::new();
+    let mut excluded = Vec::new();
+
+    for line in data.lines().map(|line| line.trim()) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // entries can point to a desktop action, e.g. "foot.desktop:server"
+        let id = |entry: &str| entry.split(':').next().unwrap_or(entry).to_string();
+
+        match line.strip_prefix('-') {
+            Some(entry) => excluded.push(id(entry)),
+            None => listed.push(id(line.trim_start_matches('+'))),
+        }
+    }
+
+    (listed, excluded)
+}
+
+// lists are read in order of priority, an exclusion also hides the entry from the lists after
+// it and from probing, which is why the exclusions are returned as well
+fn terminal_from_xdg_terminals_list() -> (Option<(Terminal, PathBuf)>, Vec<Terminal>) {
+    let mut excluded: Vec<String> = Vec::new();
+
+    for path in xdg_terminals_lists() {
+        let Ok(data) = fs::read_to_string(&path) else {
+            continue;
+        };
+
+        let (listed, list_excluded) = parse_xdg_terminals_list(&data);
+        excluded.extend(list_excluded);
+
+        let term = listed
+            .iter()
+            .filter(|id| !excluded.contains(id))
+            .filter_map(|id| terminal_from_desktop_id(id))
+            .find(|term| command_path(term).is_some());
+
+        if let Some(term) = term {
+            return (Some((term, path)), Vec::new());
+        }
     }
 
-    if let Ok(_) = which("kitty") {
-        return Terminal::Kitty;
+    let excluded = excluded
+        .iter()
+        .filter_map(|id| terminal_from_desktop_id(id))
+        .collect();
+
+    (None, excluded)
+}
+
+// $TERMINAL may contain arguments, those are kept and the command is run with -e as
+// sensible-terminal does it
+fn terminal_from_env_value(value: &str) -> Option<Terminal> {
+    let mut parts = value.split_whitespace();
+    let command = parts.next()?;
+    let arguments: Vec<String> = parts.map(|arg| arg.to_string()).collect();
+
+    if !arguments.is_empty() {
+        return Some(Terminal::Custom(CustomTerminal::Config(
+            CustomTerminalConfig {
+                command: command.to_string(),
+                exec_flag: None,
+                class_flag: None,
+                title_flag: None,
+                arguments: Some(arguments),
+            },
+        )));
     }
 
-    if let Ok(_) = which("alacritty") {
-        return Terminal::Alacritty;
+    let name = PathBuf::from(command)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.to_string())?;
+
+    Some(
+        terminal_from_command_name(&name).unwrap_or(Terminal::Custom(CustomTerminal::Command(
+            command.to_string(),
+        ))),
+    )
+}
+
+fn terminal_from_env_var() -> Option<Terminal> {
+    let term = terminal_from_env_value(&env::var("TERMINAL").ok()?)?;
+    command_path(&term).map(|_| term)
+}
+
+pub fn terminal_from_env(excluded: &[Terminal]) -> Option<Terminal> {
+    let candidates = [
+        Terminal::Ghostty,
+        Terminal::Kitty,
+        Terminal::Alacritty,
+        Terminal::Foot,
+        Terminal::Wezterm,
+        Terminal::Rio,
+        Terminal::Konsole,
+        Terminal::GnomeTerminal,
+        Terminal::GnomeConsole,
+        Terminal::Tilix,
+        Terminal::Urxvt,
+        Terminal::St,
+        Terminal::Xterm,
+    ];
+
+    let excluded: Vec<Option<String>> = excluded.iter().map(command_name).collect();
+
+    candidates
+        .into_iter()
+        .filter(|term| !excluded.contains(&command_name(term)))
+        .find(|term| command_path(term).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CustomTerminal, Terminal, command_name, exec_arguments, parse_xdg_terminals_list,
+        terminal_from_desktop_id, terminal_from_env_value,
+    };
+
+    #[test]
+    fn exclusions_in_terminals_lists_are_kept_apart() {
+        let (listed, excluded) = parse_xdg_terminals_list(
+            "# preferred\n+kitty.desktop\n-foot.desktop\n\nAlacritty.desktop:new-window\n",
+        );
+
+        assert_eq!(listed, ["kitty.desktop", "Alacritty.desktop"]);
+        assert_eq!(excluded, ["foot.desktop"]);
     }
 
-    if let Ok(_) = which("foot") {
-        return Terminal::Foot;
+    #[test]
+    fn footclient_runs_footclient() {
+        let term = terminal_from_desktop_id("footclient.desktop").unwrap();
+
+        assert_eq!(command_name(&term).as_deref(), Some("footclient"));
+        assert_eq!(
+            exec_arguments(&term, "btop", &[], Some("btop"), None),
+            ["--app-id=btop", "btop"]
+        );
     }
 
-    return Terminal::Xterm;
+    #[test]
+    fn terminal_arguments_are_kept() {
+        let Some(Terminal::Custom(CustomTerminal::Config(config))) =
+            terminal_from_env_value("kitty --single-instance")
+        else {
+            panic!("expected a custom terminal");
+        };
+
+        assert_eq!(config.command, "kitty");
+
+        let term = Terminal::Custom(CustomTerminal::Config(config));
+        assert_eq!(
+            exec_arguments(&term, "btop", &[], None, None),
+            ["--single-instance", "-e", "btop"]
+        );
+
+        assert!(matches!(
+            terminal_from_env_value("/usr/bin/alacritty"),
+            Some(Terminal::Alacritty)
+        ));
+    }
 }
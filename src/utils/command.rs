@@ -0,0 +1,78 @@
+use std::{
+    io,
+    process::{Output, Stdio},
+};
+
+use tokio::process::Command;
+use which::which;
+
+// replaces placeholders like {path} in a single pass, so values that contain placeholders
+// themselves are left alone
+pub fn expand(template: &str, placeholders: &[(&str, &str)]) -> String {
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template;
+
+    'outer: while let Some(c) = rest.chars().next() {
+        for (key, value) in placeholders {
+            if let Some(after) = rest.strip_prefix(key) {
+                expanded.push_str(value);
+                rest = after;
+                continue 'outer;
+            }
+        }
+
+        expanded.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    expanded
+}
+
+// replaces placeholders like {path} in every argument of a command
+pub fn expand_placeholders(command: &[String], placeholders: &[(&str, String)]) -> Vec<String> {
+    let placeholders: Vec<(&str, &str)> = placeholders
+        .iter()
+        .map(|(key, value)| (*key, value.as_str()))
+        .collect();
+
+    command
+        .iter()
+        .map(|arg| expand(arg, &placeholders))
+        .collect()
+}
+
+// runs a command like ["grim", "{output}"] with its placeholders replaced and waits for it to
+// exit, stdout is captured. The command is killed when the future is dropped
+pub async fn run_template(
+    command: &[String],
+    placeholders: &[(&str, String)],
+) -> io::Result<Output> {
+    let args = expand_placeholders(command, placeholders);
+
+    let Some((program, args)) = args.split_first() else {
+        return Err(io::Error::other("command is empty"));
+    };
+
+    tracing::debug!("Running {} {:?}", program, args);
+
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|err| io::Error::new(err.kind(), format!("could not run {}: {}", program, err)))
+}
+
+// the problems of an optional command, `key` is e.g. "screenshot.capture"
+pub fn validate_command(key: &str, command: &Option<Vec<String>>) -> Vec<String> {
+    match command.as_ref().map(|command| command.first()) {
+        Some(Some(program)) if which(program).is_err() => {
+            vec![format!("{}: command {:?} could not be found", key, program)]
+        }
+        Some(None) => vec![format!("{}: command is empty", key)],
+        _ => Vec::new(),
+    }
+}
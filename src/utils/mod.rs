@@ -1 +1,7 @@
+pub mod command;
 pub mod hashmap;
+pub mod options;
+#[cfg(test)]
+pub mod testing;
+pub mod uri;
+pub mod xdg;
@@ -0,0 +1,11 @@
+use std::collections::HashMap;
+
+use zvariant::Value;
+
+// a non-empty string from the options dict of a portal call
+pub fn string_option(options: &HashMap<&str, Value<'_>>, key: &str) -> Option<String> {
+    match options.get(key) {
+        Some(Value::Str(value)) if !value.is_empty() => Some(value.to_string()),
+        _ => None,
+    }
+}
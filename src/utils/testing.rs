@@ -0,0 +1,15 @@
+use zbus::{Connection, Guid, conn::Builder};
+
+// two ends of a peer to peer connection, so tests can talk to mock services without a bus
+pub async fn p2p_pair() -> (Connection, Connection) {
+    let (server, client) = tokio::net::UnixStream::pair().unwrap();
+
+    let server = Builder::unix_stream(server)
+        .server(Guid::generate())
+        .unwrap()
+        .p2p()
+        .build();
+    let client = Builder::unix_stream(client).p2p().build();
+
+    tokio::try_join!(server, client).unwrap()
+}
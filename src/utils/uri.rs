@@ -0,0 +1,72 @@
+use std::{
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+// decodes file:// uris, e.g. file:///home/user/My%20Wallpaper.png
+pub fn file_uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?.as_bytes();
+    let mut decoded = Vec::with_capacity(path.len());
+    let mut i = 0;
+
+    while i < path.len() {
+        if path[i] == b'%'
+            && let Some(byte) = path
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(path[i]);
+            i += 1;
+        }
+    }
+
+    Some(PathBuf::from(String::from_utf8_lossy(&decoded).to_string()))
+}
+
+// encodes a path as a file:// uri, the inverse of file_uri_to_path
+pub fn path_to_file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+
+    for &byte in path.as_os_str().as_bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'/' | b'-' | b'.' | b'_' | b'~') {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{file_uri_to_path, path_to_file_uri};
+
+    #[test]
+    fn file_uris_are_percent_decoded() {
+        assert_eq!(
+            file_uri_to_path("file:///home/user/My%20Wallpaper%C3%A4.png"),
+            Some(PathBuf::from("/home/user/My Wallpaperä.png"))
+        );
+        assert_eq!(
+            file_uri_to_path("file:///tmp/100%"),
+            Some(PathBuf::from("/tmp/100%"))
+        );
+        assert_eq!(file_uri_to_path("https://example.com/a.png"), None);
+    }
+
+    #[test]
+    fn paths_are_percent_encoded() {
+        let path = Path::new("/home/user/My Wallpaperä #1.png");
+        let uri = path_to_file_uri(path);
+
+        assert_eq!(uri, "file:///home/user/My%20Wallpaper%C3%A4%20%231.png");
+        assert_eq!(file_uri_to_path(&uri).as_deref(), Some(path));
+    }
+}
@@ -0,0 +1,98 @@
+use std::{
+    env, fs, io,
+    os::unix::fs::{DirBuilderExt, MetadataExt},
+    path::PathBuf,
+};
+
+use crate::constants::CONFIG_APP_NAME;
+
+fn home_dir() -> PathBuf {
+    env::var("HOME").expect("cant evaluate HOME").into()
+}
+
+fn dirs_from_env(var: &str, default: &str) -> Vec<PathBuf> {
+    env::var(var)
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or(default.to_string())
+        .split(':')
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+pub fn config_home() -> PathBuf {
+    env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home_dir().join(".config"))
+}
+
+pub fn config_dirs() -> Vec<PathBuf> {
+    dirs_from_env("XDG_CONFIG_DIRS", "/etc/xdg")
+}
+
+pub fn data_home() -> PathBuf {
+    env::var("XDG_DATA_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home_dir().join(".local").join("share"))
+}
+
+pub fn state_home() -> PathBuf {
+    env::var("XDG_STATE_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home_dir().join(".local").join("state"))
+}
+
+// the spec asks for a replacement with a warning when $XDG_RUNTIME_DIR is unset
+pub fn runtime_dir() -> PathBuf {
+    env::var("XDG_RUNTIME_DIR")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            let dir = env::temp_dir();
+            tracing::warn!("XDG_RUNTIME_DIR is not set, using {:?}", dir);
+            dir
+        })
+}
+
+// $XDG_RUNTIME_DIR/xdg-desktop-portal-zenzai/<name>, only accessible by the user
+pub fn private_runtime_dir(name: &str) -> io::Result<PathBuf> {
+    let app_dir = runtime_dir().join(CONFIG_APP_NAME);
+    let dir = app_dir.join(name);
+
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)?;
+
+    // the fallback may be shared with other users
+    let meta = fs::symlink_metadata(&app_dir)?;
+    if !meta.is_dir() || meta.uid() != unsafe { libc::getuid() } || meta.mode() & 0o077 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{:?} is not a private directory", app_dir),
+        ));
+    }
+
+    Ok(dir)
+}
+
+pub fn data_dirs() -> Vec<PathBuf> {
+    dirs_from_env("XDG_DATA_DIRS", "/usr/local/share:/usr/share")
+}
+
+pub fn current_desktops() -> Vec<String> {
+    env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .split(':')
+        .filter(|desktop| !desktop.is_empty())
+        .map(|desktop| desktop.to_string())
+        .collect()
+}